@@ -1,9 +1,9 @@
-use std::{fs, num::NonZeroUsize, path::Path};
+use std::{fs, num::NonZeroUsize, path::Path, thread};
 
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 
 use runseq::{
-    branch_bound::{BranchBound, DeiceStrategy},
+    branch_bound::{BranchBound, DeiceStrategy, LowerBound, RollingHorizon},
     instance::{solve::Solve, Instance},
 };
 
@@ -16,8 +16,9 @@ fn furini(criterion: &mut Criterion) {
     const DECOMPOSED_FURINI_INSTANCES: &[usize] = &[2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12];
 
     let branch_bound = BranchBound {
-        horizon: HORIZON,
+        horizon: HORIZON.map(RollingHorizon::flights),
         deice_strategy: DeiceStrategy::ByTobt,
+        ..BranchBound::default()
     };
 
     for &id in DECOMPOSED_FURINI_INSTANCES {
@@ -32,8 +33,9 @@ fn furini(criterion: &mut Criterion) {
     const INTEGRATED_FURINI_INSTANCES: &[usize] = &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12];
 
     let branch_bound = BranchBound {
-        horizon: HORIZON,
+        horizon: HORIZON.map(RollingHorizon::flights),
         deice_strategy: DeiceStrategy::Integrated,
+        ..BranchBound::default()
     };
 
     for &id in INTEGRATED_FURINI_INSTANCES {
@@ -57,8 +59,9 @@ fn heathrow(criterion: &mut Criterion) {
     ];
 
     let branch_bound = BranchBound {
-        horizon: HORIZON,
+        horizon: HORIZON.map(RollingHorizon::flights),
         deice_strategy: DeiceStrategy::ByTobt,
+        ..BranchBound::default()
     };
 
     for &id in TOBT_HEATHROW_INSTANCES {
@@ -76,8 +79,9 @@ fn heathrow(criterion: &mut Criterion) {
     ];
 
     let branch_bound = BranchBound {
-        horizon: HORIZON,
+        horizon: HORIZON.map(RollingHorizon::flights),
         deice_strategy: DeiceStrategy::ByCtot,
+        ..BranchBound::default()
     };
 
     for &id in CTOT_HEATHROW_INSTANCES {
@@ -95,8 +99,9 @@ fn heathrow(criterion: &mut Criterion) {
     ];
 
     let branch_bound = BranchBound {
-        horizon: HORIZON,
+        horizon: HORIZON.map(RollingHorizon::flights),
         deice_strategy: DeiceStrategy::Integrated,
+        ..BranchBound::default()
     };
 
     for &id in INTEGRATED_HEATHROW_INSTANCES {
@@ -107,13 +112,59 @@ fn heathrow(criterion: &mut Criterion) {
             |bencher, instance| bencher.iter(|| branch_bound.solve(instance)),
         );
     }
+
+    let branch_bound = BranchBound {
+        horizon: HORIZON.map(RollingHorizon::flights),
+        deice_strategy: DeiceStrategy::Integrated,
+        threads: thread::available_parallelism().ok(),
+        ..BranchBound::default()
+    };
+
+    for &id in INTEGRATED_HEATHROW_INSTANCES {
+        let instance = load_instance(format!("../instances/heathrow/toml/{}.toml", id));
+        group.bench_with_input(
+            BenchmarkId::new("parallel integrated de-icing", id),
+            &instance,
+            |bencher, instance| bencher.iter(|| branch_bound.solve(instance)),
+        );
+    }
+}
+
+// NOTE: Criterion only measures time, so the number of nodes explored with each lower bound is reported by the
+//       `lower_bound_nodes` example instead.
+fn lower_bounds(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("lower bounds");
+
+    const HORIZON: Option<NonZeroUsize> = NonZeroUsize::new(10);
+
+    const HEATHROW_INSTANCES: &[usize] = &[
+        1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25,
+        26, 27, 28, 29, 30,
+    ];
+
+    for (name, lower_bound) in [("simple", LowerBound::Simple), ("tight", LowerBound::Tight)] {
+        let branch_bound = BranchBound {
+            horizon: HORIZON.map(RollingHorizon::flights),
+            deice_strategy: DeiceStrategy::Integrated,
+            lower_bound,
+            ..BranchBound::default()
+        };
+
+        for &id in HEATHROW_INSTANCES {
+            let instance = load_instance(format!("../instances/heathrow/toml/{}.toml", id));
+            group.bench_with_input(
+                BenchmarkId::new(name, id),
+                &instance,
+                |bencher, instance| bencher.iter(|| branch_bound.solve(instance)),
+            );
+        }
+    }
 }
 
 fn load_instance(path: impl AsRef<Path>) -> Instance {
     let toml = fs::read_to_string(path).unwrap();
-    let instance = toml::from_str::<Instance>(&toml).unwrap();
-    instance
+    toml::from_str::<Instance>(&toml).unwrap()
 }
 
-criterion_group!(benches, furini, heathrow);
+criterion_group!(benches, furini, heathrow, lower_bounds);
 criterion_main!(benches);
@@ -3,16 +3,17 @@ use std::{fs, num::NonZeroUsize, path::Path};
 use csv::Writer;
 
 use runseq::{
-    branch_bound::{self, BranchBound, DeiceStrategy},
-    instance::{schedule::Schedule, Instance},
+    branch_bound::{self, BranchBound, DeiceStrategy, RollingHorizon},
+    instance::Instance,
     vis::Visualiser,
 };
 
 fn main() {
     let deice_strategy = DeiceStrategy::Integrated;
     let branch_bound = BranchBound {
-        horizon: NonZeroUsize::new(10),
+        horizon: NonZeroUsize::new(10).map(RollingHorizon::flights),
         deice_strategy,
+        ..BranchBound::default()
     };
 
     let vis = Visualiser::new();
@@ -35,74 +36,40 @@ fn main() {
         let toml = fs::read_to_string(instance_path).unwrap();
         let instance = toml::from_str::<Instance>(&toml).unwrap();
 
-        let Some(solution) = instance.solve_with(&branch_bound) else {
-            println!("unable to solve instance {}", id);
-            csv.write_record([&format!("FPT{:0>2}", id), "", "", "", "", ""])
-                .unwrap();
-            continue;
+        let solution = match instance.solve_with(&branch_bound) {
+            Ok(solution) => solution,
+            Err(err) => {
+                println!("unable to solve instance {}: {}", id, err);
+                csv.write_record([&format!("FPT{:0>2}", id), "", "", "", "", ""])
+                    .unwrap();
+                continue;
+            },
         };
 
-        let start = solution
-            .iter()
-            .map(|sched| sched.flight_time())
-            .min()
-            .unwrap();
-        let end = solution
-            .iter()
-            .map(|sched| sched.flight_time())
-            .max()
-            .unwrap();
-        let makespan = end - start;
+        // NOTE: Metrics are always calculated for solutions produced by the branch-and-bound solver.
+        let metrics = solution.metrics.as_ref().unwrap();
 
-        let deice_start = solution
-            .iter()
-            .filter_map(Schedule::as_departure)
-            .filter_map(|sched| sched.deice)
-            .min()
-            .unwrap()
-            .time();
-        let deice_end = solution
-            .iter()
-            .filter_map(Schedule::as_departure)
-            .filter_map(|sched| sched.deice)
-            .max()
-            .unwrap()
-            .time();
-
-        let cost = branch_bound::solution_cost(&solution, &instance);
-
-        let runway_hold = solution
-            .iter()
-            .filter_map(|sched| {
-                let sched = sched.as_departure()?;
-                let dep = instance.flights()[sched.flight_index].as_departure()?;
-
-                let deice_time = sched.deice.as_ref().copied()?;
-                let deice_dur = dep.deice.as_ref()?.duration;
-
-                let runway_hold = sched.takeoff
-                    - dep.lineup_duration
-                    - dep.taxi_duration
-                    - deice_dur
-                    - deice_time;
-
-                Some(runway_hold.num_seconds().unsigned_abs())
-            })
-            .sum::<u64>();
+        let cost = branch_bound::solution_cost(&solution.schedules, &instance);
 
         csv.write_record([
             format!("FPT{:0>2}", id),
-            makespan.num_seconds().to_string(),
-            deice_start.to_string(),
-            deice_end.to_string(),
+            metrics.makespan.as_secs().to_string(),
+            metrics
+                .deice_start
+                .map(|deice_start| deice_start.time().to_string())
+                .unwrap_or_default(),
+            metrics
+                .deice_end
+                .map(|deice_end| deice_end.time().to_string())
+                .unwrap_or_default(),
             cost.as_u64().to_string(),
-            runway_hold.to_string(),
+            metrics.runway_hold.as_secs().to_string(),
         ])
         .unwrap();
 
         println!("cost of solution to instance {} = {:?}", id, cost);
 
-        let doc = vis.visualise(&solution, &instance).unwrap();
+        let doc = vis.visualise(&solution.schedules, &instance).unwrap();
         svg::save(format!("../visuals/furini/{}.svg", id), &doc).unwrap();
     }
 
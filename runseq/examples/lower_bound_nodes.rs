@@ -0,0 +1,36 @@
+use std::{fs, num::NonZeroUsize, path::Path};
+
+use runseq::{
+    branch_bound::{BranchBound, DeiceStrategy, LowerBound, RollingHorizon},
+    instance::Instance,
+};
+
+fn main() {
+    for (name, lower_bound) in [("simple", LowerBound::Simple), ("tight", LowerBound::Tight)] {
+        let branch_bound = BranchBound {
+            horizon: NonZeroUsize::new(10).map(RollingHorizon::flights),
+            deice_strategy: DeiceStrategy::Integrated,
+            lower_bound,
+            ..BranchBound::default()
+        };
+
+        println!("solving using lower bound = {}", name);
+
+        for id in 1..=30 {
+            let instance_path =
+                Path::new("../instances/heathrow/toml/").join(format!("{}.toml", id));
+            let toml = fs::read_to_string(instance_path).unwrap();
+            let instance = toml::from_str::<Instance>(&toml).unwrap();
+
+            match instance.solve_with(&branch_bound) {
+                // NOTE: The number of nodes explored is always reported by the branch-and-bound solver.
+                Ok(solution) => println!(
+                    "nodes explored for instance {} = {}",
+                    id,
+                    solution.nodes.unwrap()
+                ),
+                Err(err) => println!("unable to solve instance {}: {}", id, err),
+            }
+        }
+    }
+}
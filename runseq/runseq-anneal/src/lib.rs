@@ -0,0 +1,328 @@
+//! A simulated annealing metaheuristic for integrated runway sequencing and de-icing.
+
+#![deny(rust_2018_idioms)]
+#![warn(missing_docs)]
+
+use std::{
+    collections::BTreeMap,
+    num::NonZeroUsize,
+    time::{Duration, Instant},
+};
+
+use runseq_branch_bound::{decode, Cost, Objective};
+use runseq_instance::{
+    schedule::Schedule,
+    solution::{Solution, SolverInfo},
+    solve::{Solve, SolveError},
+    Instance,
+};
+
+mod rng;
+use rng::Rng;
+
+// The ratio between the final and initial temperature
+const FINAL_TEMPERATURE_RATIO: f64 = 1e-3;
+
+// The number of random moves used to estimate the initial temperature
+const TEMPERATURE_SAMPLES: usize = 100;
+
+/// A simulated annealing solver for solving [`Instance`]s, meant for instances that are too large for exact search.
+///
+/// The solver searches over orders of aircraft, starting from the first of these orders that can be turned into a
+/// feasible sequence:
+///
+/// 1. The [initial order](SimulatedAnnealing::initial_order), if any.
+/// 2. The [first-come-first-served order](Instance::fcfs_positions).
+/// 3. The order of the latest times in the aircraft's time windows.
+///
+/// Whenever an aircraft cannot be feasibly scheduled in one of these orders, it is moved to the closest earlier
+/// position that lets more aircraft be scheduled, for as long as there is one.
+///
+/// Every order is turned into a sequence using [`decode`], so it uses the same timing logic as
+/// [`BranchBound`](runseq_branch_bound::BranchBound) with integrated de-icing and only ever produces feasible
+/// sequences.
+/// Moves that swap two aircraft or move an aircraft to another position are always accepted if they do not make the
+/// sequence worse, and are otherwise accepted with a probability that shrinks as the search cools down.
+///
+/// The search stops once it has made the given number of moves or reaches its time limit, and cools down according
+/// to whichever of the two it is closer to.
+/// Given the same seed, the solver always produces the same sequence unless it is stopped by its time limit.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct SimulatedAnnealing {
+    /// The objective function to minimise.
+    pub objective: Objective,
+    /// The seed used to generate random moves.
+    pub seed: u64,
+    /// The number of moves to make.
+    pub iterations: u64,
+    /// The maximum wall-clock time to spend solving an instance, if any.
+    pub time_limit: Option<Duration>,
+    /// The maximum number of positions that an aircraft can be moved by in a single move.
+    pub max_distance: NonZeroUsize,
+    /// The indices of the aircraft in the order to start the search from, if any, such as the order of a known
+    /// sequence.
+    ///
+    /// The order is ignored unless it contains every aircraft in the instance exactly once.
+    pub initial_order: Option<Vec<usize>>,
+}
+
+impl Default for SimulatedAnnealing {
+    fn default() -> Self {
+        Self {
+            objective: Objective::default(),
+            seed: 0,
+            iterations: 10_000,
+            time_limit: None,
+            max_distance: NonZeroUsize::new(8).unwrap(),
+            initial_order: None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+enum Move {
+    Swap(usize, usize),
+    Insert(usize, usize),
+}
+
+impl Move {
+    fn random(rng: &mut Rng, len: usize, max_distance: usize) -> Self {
+        // Pick a second position within the maximum distance of the first one, skipping over the first one itself
+        let from = rng.below(len);
+        let start = from.saturating_sub(max_distance);
+        let end = (len - 1).min(from + max_distance);
+        let mut to = start + rng.below(end - start);
+        if to >= from {
+            to += 1;
+        }
+
+        match rng.below(2) {
+            0 => Self::Swap(from, to),
+            _ => Self::Insert(from, to),
+        }
+    }
+
+    fn apply(self, order: &[usize]) -> Vec<usize> {
+        let mut order = order.to_vec();
+        match self {
+            Self::Swap(first, second) => order.swap(first, second),
+            Self::Insert(from, to) => {
+                let flight_idx = order.remove(from);
+                order.insert(to, flight_idx);
+            },
+        }
+        order
+    }
+}
+
+impl SimulatedAnnealing {
+    fn evaluate(&self, order: &[usize], instance: &Instance) -> Option<(Vec<Schedule>, Cost)> {
+        let solution = decode(order, instance, &self.objective).ok()?;
+        let cost = self.objective.solution_cost(&solution, instance);
+        Some((solution, cost))
+    }
+
+    // Finds the first order to start the search from that can be decoded into a feasible sequence, returning the
+    // error for the first-come-first-served order if there is none
+    fn initial(&self, instance: &Instance) -> Result<(Vec<usize>, Vec<Schedule>), SolveError> {
+        let mut fcfs = (0..instance.flights().len()).collect::<Vec<_>>();
+        let positions = instance.fcfs_positions();
+        fcfs.sort_by_key(|&flight_idx| positions[flight_idx]);
+
+        // NOTE: Aircraft without a time window are put last, since they can never miss it.
+        let mut by_latest = fcfs.clone();
+        by_latest.sort_by_key(|&flight_idx| {
+            let window = instance.flights()[flight_idx].window();
+            (window.is_none(), window.map(|window| window.latest()))
+        });
+
+        // NOTE: An initial order that does not contain every aircraft exactly once would lead to an incomplete
+        //       sequence, so it is ignored.
+        let initial_order = self
+            .initial_order
+            .as_ref()
+            .filter(|order| is_permutation(order, instance.flights().len()));
+        if let Some(order) = initial_order {
+            if let Ok(initial) = self.repair(order.clone(), instance) {
+                return Ok(initial);
+            }
+        }
+        match self.repair(fcfs, instance) {
+            Ok(initial) => Ok(initial),
+            Err(error) => self.repair(by_latest, instance).map_err(|_| error),
+        }
+    }
+
+    // Moves every aircraft that cannot be feasibly scheduled to the latest earlier position in the order that lets more
+    // aircraft be scheduled, until the order can be decoded or until no such position exists
+    // NOTE: Every move schedules more aircraft than before, so there are at most as many moves as aircraft.
+    fn repair(
+        &self,
+        mut order: Vec<usize>,
+        instance: &Instance,
+    ) -> Result<(Vec<usize>, Vec<Schedule>), SolveError> {
+        let mut error = match decode(&order, instance, &self.objective) {
+            Ok(solution) => return Ok((order, solution)),
+            Err(error) => error,
+        };
+
+        loop {
+            let SolveError::Infeasible { window, flights } = &error else {
+                return Err(error);
+            };
+            let Some(position) = flights.first().and_then(|flight| {
+                order
+                    .iter()
+                    .position(|&flight_idx| flight_idx == flight.flight_index)
+            }) else {
+                return Err(error);
+            };
+            let depth = window.start;
+
+            let mut repaired = None;
+            for target in (0..position).rev() {
+                let mut candidate = order.clone();
+                let flight_idx = candidate.remove(position);
+                candidate.insert(target, flight_idx);
+                match decode(&candidate, instance, &self.objective) {
+                    Ok(solution) => return Ok((candidate, solution)),
+                    Err(next) if infeasible_from(&next).is_some_and(|next| next > depth) => {
+                        repaired = Some((candidate, next));
+                        break;
+                    },
+                    Err(_) => {},
+                }
+            }
+
+            match repaired {
+                Some((candidate, next)) => (order, error) = (candidate, next),
+                None => return Err(error),
+            }
+        }
+    }
+
+    // Estimates a temperature at which most moves that make the initial sequence worse are accepted, using the average
+    // amount by which a number of random moves make it worse
+    fn initial_temperature(
+        &self,
+        rng: &mut Rng,
+        order: &[usize],
+        cost: Cost,
+        instance: &Instance,
+    ) -> f64 {
+        let deltas = (0..TEMPERATURE_SAMPLES)
+            .filter_map(|_| {
+                let candidate =
+                    Move::random(rng, order.len(), self.max_distance.get()).apply(order);
+                let (_, candidate_cost) = self.evaluate(&candidate, instance)?;
+                (candidate_cost > cost).then(|| (candidate_cost.as_u64() - cost.as_u64()) as f64)
+            })
+            .collect::<Vec<_>>();
+
+        match deltas.is_empty() {
+            true => 1.0,
+            false => (deltas.iter().sum::<f64>() / deltas.len() as f64).max(1.0),
+        }
+    }
+
+    fn solver_info(&self) -> SolverInfo {
+        let mut settings = BTreeMap::from([
+            ("seed".to_owned(), self.seed.to_string()),
+            ("iterations".to_owned(), self.iterations.to_string()),
+            ("max-distance".to_owned(), self.max_distance.to_string()),
+        ]);
+        if let Some(initial_order) = &self.initial_order {
+            settings.insert("initial-order".to_owned(), format!("{:?}", initial_order));
+        }
+        if let Some(time_limit) = self.time_limit {
+            settings.insert("time-limit".to_owned(), format!("{:?}", time_limit));
+        }
+        if self.objective != Objective::default() {
+            settings.insert("objective".to_owned(), format!("{:?}", self.objective));
+        }
+
+        SolverInfo {
+            name: "simulated-annealing".to_owned(),
+            settings,
+        }
+    }
+}
+
+impl Solve for SimulatedAnnealing {
+    fn solve(&self, instance: &Instance) -> Result<Solution, SolveError> {
+        let start = Instant::now();
+
+        let mut rng = Rng::new(self.seed);
+
+        let (mut order, mut best_solution) = self.initial(instance)?;
+        let mut best_cost = self.objective.solution_cost(&best_solution, instance);
+        let mut current_cost = best_cost;
+
+        // NOTE: There are no moves to make with fewer than two aircraft.
+        if order.len() >= 2 {
+            let initial_temperature =
+                self.initial_temperature(&mut rng, &order, current_cost, instance);
+
+            for iteration in 0..self.iterations {
+                // Cool down according to whichever budget is closer to running out
+                let mut progress = iteration as f64 / self.iterations as f64;
+                if let Some(time_limit) = self.time_limit {
+                    let elapsed = start.elapsed();
+                    if elapsed >= time_limit {
+                        break;
+                    }
+                    progress = progress.max(elapsed.as_secs_f64() / time_limit.as_secs_f64());
+                }
+                let temperature = initial_temperature * FINAL_TEMPERATURE_RATIO.powf(progress);
+
+                let candidate =
+                    Move::random(&mut rng, order.len(), self.max_distance.get()).apply(&order);
+                let Some((solution, cost)) = self.evaluate(&candidate, instance) else {
+                    continue;
+                };
+
+                // Always accept moves that do not make the sequence worse, and accept other moves with a
+                // probability based on how much worse they make it
+                let accepted = match cost <= current_cost {
+                    true => true,
+                    false => {
+                        let delta = cost.as_u64().saturating_sub(current_cost.as_u64()) as f64;
+                        rng.unit() < (-delta / temperature).exp()
+                    },
+                };
+                if !accepted {
+                    continue;
+                }
+
+                order = candidate;
+                current_cost = cost;
+                if cost < best_cost {
+                    best_solution = solution;
+                    best_cost = cost;
+                }
+            }
+        }
+
+        let mut solution = Solution::new(best_solution, instance);
+        solution.solver = Some(self.solver_info());
+        solution.wall_time = Some(start.elapsed());
+
+        Ok(solution)
+    }
+}
+
+// Returns the first position in the sequence that could not be filled, if the error is due to infeasibility
+fn infeasible_from(error: &SolveError) -> Option<usize> {
+    match error {
+        SolveError::Infeasible { window, .. } => Some(window.start),
+        _ => None,
+    }
+}
+
+fn is_permutation(order: &[usize], len: usize) -> bool {
+    let mut seen = vec![false; len];
+    order.len() == len
+        && order
+            .iter()
+            .all(|&flight_idx| flight_idx < len && !std::mem::replace(&mut seen[flight_idx], true))
+}
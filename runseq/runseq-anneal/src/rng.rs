@@ -0,0 +1,35 @@
+// NOTE: A small pseudo-random number generator is used rather than an external crate, so that the sequence of random
+//       numbers (and thus every solution) for a given seed never changes between versions.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    // Seeds the generator using SplitMix64, which spreads similar seeds apart and never produces an all-zero state
+    pub fn new(seed: u64) -> Self {
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        Self { state: z | 1 }
+    }
+
+    // Generates the next number using xorshift64*
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    // Generates a number in `0..bound`, with a negligible bias for the small bounds used here
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    // Generates a number in `0.0..1.0`
+    pub fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
@@ -0,0 +1,671 @@
+use std::{collections::BTreeMap, time::Instant};
+
+use runseq_instance::{
+    flight::{Deice, Departure, Flight},
+    schedule::{DepartureSchedule, Schedule},
+    solution::{Solution, SolverInfo},
+    solve::{Constraint, Solve, SolveError},
+    Instance,
+};
+
+use crate::{
+    cost::{schedule_cost, Cost},
+    integrated,
+    objective::Objective,
+    search::{deice_pad_ends, feasible, BranchBoundState, Node, Rejections},
+    BranchBound,
+};
+
+/// An exhaustive solver for solving very small [`Instance`]s, meant as a reference for checking the optimality of
+/// other solvers.
+///
+/// Every order of the aircraft is tried, scheduling each aircraft as early as possible after the aircraft before it
+/// on every allowed runway.
+/// Every departure that needs to be de-iced is tried on every de-icing pad and at every minute that it can be de-iced
+/// at, rather than only on the pad that becomes free the earliest.
+///
+/// Like every other solver, it only considers sequences in which every aircraft lands or takes off as early as
+/// possible after the aircraft before it, and in which departures are de-iced in the order they take off in, so it
+/// never fills gaps between earlier de-icing operations on a pad.
+/// The sequences it produces are marked as optimal unless the objective can reward landing or taking off later than
+/// possible, as [`Objective::ctot_early`] and [`Shift::Time`](crate::Shift::Time) stability do, in which case a
+/// cheaper sequence may exist outside of the sequences it considers.
+/// None of the rules used by [`BranchBound`] to discard parts of the search tree are used, except for discarding
+/// partial sequences that already cost at least as much as the best sequence found so far.
+///
+/// Aircraft with [pinned](runseq_instance::pin::Pin) schedules are always sequenced first, and departures with pinned
+/// de-icing slots are only tried in their slots.
+/// Aircraft are never moved further from their first-come-first-served positions than allowed by
+/// [`Instance::max_position_shift`].
+///
+/// The number of sequences grows factorially with the number of aircraft, so this is only practical for instances of
+/// up to roughly 8 aircraft.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct BruteForce {
+    /// The objective function to minimise.
+    pub objective: Objective,
+}
+
+/// The result of comparing the sequence produced by [`BranchBound`] against the optimal sequence produced by
+/// [`BruteForce`], as returned by [`compare_with_brute_force`].
+#[derive(Debug)]
+pub struct Comparison {
+    /// The optimal sequence.
+    pub optimal: Solution,
+    /// The cost of the optimal sequence.
+    pub optimal_cost: Cost,
+    /// The sequence produced by branch-and-bound, or the error it returned.
+    pub branch_bound: Result<Solution, SolveError>,
+    /// The cost of the sequence produced by branch-and-bound, if any.
+    pub branch_bound_cost: Option<Cost>,
+}
+
+impl Comparison {
+    /// Returns the amount by which the objective value of the branch-and-bound sequence exceeds the optimal objective
+    /// value, or [`None`] if branch-and-bound did not produce a sequence at all.
+    pub fn gap(&self) -> Option<u64> {
+        self.branch_bound_cost
+            .map(|cost| cost.as_u64().saturating_sub(self.optimal_cost.as_u64()))
+    }
+
+    /// Checks if branch-and-bound produced a sequence with the optimal objective value.
+    pub fn is_optimal(&self) -> bool {
+        self.gap() == Some(0)
+    }
+}
+
+/// Solves an instance with both [`BruteForce`] and a [`BranchBound`] solver without a rolling horizon, and compares
+/// the sequences they produce.
+///
+/// The branch-and-bound solver uses integrated de-icing and the given objective function, and is otherwise configured
+/// with its defaults.
+/// Any gap between the two means that branch-and-bound discarded every optimal sequence, or could not represent one.
+/// The comparison is only conclusive if the optimal sequence is [marked as optimal](Solution::optimal).
+///
+/// Returns a [`SolveError`] if the instance has no feasible sequence at all.
+pub fn compare_with_brute_force(
+    instance: &Instance,
+    objective: &Objective,
+) -> Result<Comparison, SolveError> {
+    let optimal = BruteForce {
+        objective: objective.clone(),
+    }
+    .solve(instance)?;
+    let optimal_cost = objective.solution_cost(&optimal.schedules, instance);
+
+    let branch_bound = BranchBound {
+        objective: objective.clone(),
+        ..BranchBound::default()
+    }
+    .solve(instance);
+    let branch_bound_cost = branch_bound
+        .as_ref()
+        .ok()
+        .map(|solution| objective.solution_cost(&solution.schedules, instance));
+
+    Ok(Comparison {
+        optimal,
+        optimal_cost,
+        branch_bound,
+        branch_bound_cost,
+    })
+}
+
+#[derive(Debug, Default)]
+struct Search {
+    scheduled: Vec<bool>,
+    best: Option<(Cost, Vec<Schedule>)>,
+    rejections: Rejections,
+    nodes: u64,
+}
+
+impl BruteForce {
+    fn search(
+        &self,
+        instance: &Instance,
+        state: &mut BranchBoundState,
+        cost: Cost,
+        search: &mut Search,
+    ) {
+        let depth = state.current_solution.len();
+        if depth == instance.flights().len() {
+            let solution = state
+                .current_solution
+                .iter()
+                .map(|node| node.sched.clone())
+                .collect();
+            search.best = Some((cost, solution));
+            return;
+        }
+
+        for (flight_idx, flight) in instance.flights().iter().enumerate() {
+            if search.scheduled[flight_idx] {
+                continue;
+            }
+
+            if state
+                .position_shift
+                .as_ref()
+                .is_some_and(|shift| !shift.allows(flight_idx, &state.current_solution))
+            {
+                search
+                    .rejections
+                    .record(depth, flight_idx, Constraint::PositionShift);
+                continue;
+            }
+
+            let scheds = match expand(flight, flight_idx, instance, state) {
+                Ok(scheds) => scheds,
+                Err(constraint) => {
+                    search.rejections.record(depth, flight_idx, constraint);
+                    continue;
+                },
+            };
+
+            for sched in scheds {
+                search.nodes += 1;
+
+                // NOTE: The cost of every aircraft is non-negative, so a partial sequence that already costs as much as
+                //       the best sequence can never lead to a cheaper one.
+                let sched_cost = schedule_cost(
+                    &sched,
+                    depth,
+                    instance,
+                    &self.objective,
+                    &state.reference_positions,
+                );
+                let next_cost = cost + sched_cost;
+                if search
+                    .best
+                    .as_ref()
+                    .is_some_and(|(best_cost, _)| next_cost >= *best_cost)
+                {
+                    continue;
+                }
+
+                state.current_solution.push(Node {
+                    sched,
+                    depth,
+                    complete_order_idx: 0,
+                    cost: sched_cost,
+                });
+                search.scheduled[flight_idx] = true;
+
+                self.search(instance, state, next_cost, search);
+
+                search.scheduled[flight_idx] = false;
+                state.current_solution.pop();
+            }
+        }
+    }
+
+    fn solver_info(&self) -> SolverInfo {
+        let mut settings = BTreeMap::new();
+        if self.objective != Objective::default() {
+            settings.insert("objective".to_owned(), format!("{:?}", self.objective));
+        }
+
+        SolverInfo {
+            name: "brute-force".to_owned(),
+            settings,
+        }
+    }
+}
+
+impl Solve for BruteForce {
+    fn solve(&self, instance: &Instance) -> Result<Solution, SolveError> {
+        let start = Instant::now();
+
+        let flight_count = instance.flights().len();
+
+        let mut state = BranchBoundState::without_complete_orders(instance, &self.objective);
+        let mut search = Search {
+            scheduled: vec![false; flight_count],
+            ..Search::default()
+        };
+        let mut cost = Cost::default();
+        for node in &state.current_solution {
+            search.scheduled[node.sched.flight_index()] = true;
+            cost += node.cost;
+        }
+
+        self.search(instance, &mut state, cost, &mut search);
+
+        let Some((_, schedules)) = search.best else {
+            let depth = search.rejections.depth();
+            return Err(SolveError::Infeasible {
+                window: depth..flight_count,
+                flights: search.rejections.into_infeasible_flights(),
+            });
+        };
+
+        let mut solution = Solution::new(schedules, instance);
+        solution.solver = Some(self.solver_info());
+        solution.wall_time = Some(start.elapsed());
+        solution.nodes = Some(search.nodes);
+        solution.optimal = !self.objective.rewards_later_times();
+
+        Ok(solution)
+    }
+}
+
+pub fn expand(
+    flight: &Flight,
+    flight_idx: usize,
+    instance: &Instance,
+    state: &BranchBoundState,
+) -> Result<Vec<Schedule>, Constraint> {
+    match flight {
+        Flight::Dep(
+            dep @ Departure {
+                deice: Some(deice), ..
+            },
+        ) if instance.pin(flight_idx).is_none() => Ok(expand_deiced_departure(
+            dep, flight_idx, deice, instance, state,
+        )?
+        .into_iter()
+        .map(Schedule::Dep)
+        .collect()),
+        // NOTE: Arrivals, departures that do not need to be de-iced, and departures with pinned de-icing slots are
+        //       scheduled exactly like in branch-and-bound.
+        _ => integrated::expand(flight, flight_idx, instance, state),
+    }
+}
+
+fn expand_deiced_departure(
+    dep: &Departure,
+    flight_index: usize,
+    deice: &Deice,
+    instance: &Instance,
+    state: &BranchBoundState,
+) -> Result<Vec<DepartureSchedule>, Constraint> {
+    let pad_ends = deice_pad_ends(instance, state);
+    feasible(pad_ends.into_iter().enumerate().flat_map(|pad| {
+        integrated::expand_deiced_departure_at(dep, flight_index, deice, pad, instance, state)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{num::NonZeroUsize, time::Duration};
+
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    use runseq_instance::{
+        deice::DeicePad,
+        flight::{Arrival, Ctot, Deice, Departure, Flight, TimeWindow},
+        runway::{Runway, RunwayPair, RunwayRelation},
+        sep::Separations,
+        solve::Solve,
+        Instance,
+    };
+
+    use super::{compare_with_brute_force, BruteForce};
+    use crate::{BranchBound, DynamicProgram, Objective, Penalty, RollingHorizon, Shift, Term};
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    // The resources and constraints of a generated instance
+    #[derive(Debug, Copy, Clone)]
+    struct Layout {
+        runways: usize,
+        deice_pads: usize,
+        windows: bool,
+    }
+
+    const SINGLE: Layout = Layout {
+        runways: 1,
+        deice_pads: 1,
+        windows: false,
+    };
+
+    fn start() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, 1)
+            .and_then(|date| date.and_hms_opt(10, 0, 0))
+            .unwrap()
+    }
+
+    // Generates a small instance with a mix of arrivals, de-iced departures, and departures with CTOT slots, whose
+    // separations depend on the weight classes of the aircraft and do not all satisfy the triangle inequality
+    fn instance(flight_count: usize, mut seed: u64, layout: Layout) -> Instance {
+        let mut random = |bound: u64| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed % bound
+        };
+
+        let mut flights = Vec::new();
+        let mut classes = Vec::new();
+        for idx in 0..flight_count {
+            let release = start() + MINUTE * (idx as u32 + random(2) as u32);
+            classes.push(random(3));
+            let window = (layout.windows && random(2) == 0).then(|| TimeWindow {
+                earliest: release,
+                duration: MINUTE * (1 + random(4) as u32),
+            });
+            let runways = (layout.runways > 1 && random(3) == 0)
+                .then(|| vec![random(layout.runways as u64) as usize]);
+            let flight = match random(3) {
+                0 => Flight::Arr(Arrival {
+                    earliest_time: release,
+                    base_time: release,
+                    window,
+                    runways,
+                    info: None,
+                }),
+                kind => Flight::Dep(Departure {
+                    earliest_time: release,
+                    base_time: release,
+                    tobt: release - MINUTE * 25,
+                    pushback_duration: MINUTE * 5,
+                    deice: (kind == 1).then_some(Deice {
+                        taxi_duration: MINUTE * 5,
+                        duration: MINUTE * 3,
+                        hot: MINUTE * 20,
+                    }),
+                    taxi_duration: MINUTE * 5,
+                    lineup_duration: MINUTE * 5,
+                    ctot: (random(4) == 0).then_some(Ctot {
+                        target: release + MINUTE * 3,
+                        allow_early: MINUTE * 5,
+                        allow_late: MINUTE * 10,
+                    }),
+                    window,
+                    runways,
+                    info: None,
+                }),
+            };
+            flights.push(flight);
+        }
+
+        let separations = (0..flight_count)
+            .flat_map(|from| (0..flight_count).map(move |to| (from, to)))
+            .map(|(from, to)| match from == to {
+                true => Duration::ZERO,
+                false => MINUTE + MINUTE * (classes[from] * (2 - classes[to])) as u32,
+            })
+            .collect::<Vec<_>>();
+
+        let relations = [
+            RunwayRelation::Independent,
+            RunwayRelation::Dependent,
+            RunwayRelation::Segregated,
+        ];
+        let runway_pairs = (1..layout.runways)
+            .map(|second| RunwayPair {
+                first: 0,
+                second,
+                relation: relations[random(3) as usize],
+            })
+            .collect::<Vec<_>>();
+
+        // NOTE: Every de-icing pad after the first is slower, so that the choice of pad matters.
+        let deice_pads = (0..layout.deice_pads)
+            .map(|pad| DeicePad {
+                name: None,
+                duration: (pad > 0).then_some(MINUTE * (3 + pad as u32)),
+            })
+            .collect::<Vec<_>>();
+
+        Instance::with_runways(
+            flights,
+            Separations::new(separations, flight_count).unwrap(),
+            vec![Runway::default(); layout.runways],
+            runway_pairs,
+            MINUTE * 10,
+        )
+        .and_then(|instance| instance.with_deice_pads(deice_pads))
+        .unwrap()
+    }
+
+    // Checks that a solver finds a feasible sequence exactly as cheap as the brute-force optimum, or no sequence at all
+    // if brute force does not find one either
+    fn assert_optimal<S>(solver: &S, instance: &Instance, objective: &Objective, seed: u64)
+    where
+        S: Solve,
+    {
+        let optimal = BruteForce {
+            objective: objective.clone(),
+        }
+        .solve(instance);
+        let solution = solver.solve(instance);
+        match (optimal, solution) {
+            (Ok(optimal), Ok(solution)) => {
+                assert!(optimal.optimal, "seed {seed}");
+                assert!(
+                    solution
+                        .violations
+                        .iter()
+                        .flatten()
+                        .all(|violation| objective.penalises(violation)),
+                    "seed {seed}: {:?}",
+                    solution.violations,
+                );
+                assert_eq!(
+                    objective.solution_cost(&solution.schedules, instance),
+                    objective.solution_cost(&optimal.schedules, instance),
+                    "seed {seed}: {:?} instead of {:?}",
+                    solution.schedules,
+                    optimal.schedules,
+                );
+            },
+            (Err(_), Err(_)) => {},
+            (optimal, solution) => {
+                panic!(
+                    "seed {seed}: brute force gives {optimal:?}, but the solver gives {solution:?}"
+                )
+            },
+        }
+    }
+
+    fn objectives() -> [Objective; 3] {
+        [
+            Objective::default(),
+            Objective {
+                delay: Term::new(1, Penalty::Linear),
+                runway_hold_in_objective: true,
+                ..Objective::default()
+            },
+            Objective {
+                window_late: Some(Term::new(10, Penalty::Linear)),
+                ..Objective::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn branch_bound_matches_brute_force() {
+        for objective in &objectives()[..2] {
+            for seed in 1..=8 {
+                let comparison =
+                    compare_with_brute_force(&instance(6, seed, SINGLE), objective).unwrap();
+                assert!(comparison.optimal.optimal);
+                assert!(
+                    comparison.is_optimal(),
+                    "seed {seed}: branch-and-bound is {:?} above the optimum",
+                    comparison.gap(),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn dynamic_program_matches_brute_force() {
+        let objective = Objective::default();
+        let solver = DynamicProgram::default();
+        for seed in 1..=8 {
+            assert_optimal(&solver, &instance(6, seed, SINGLE), &objective, seed);
+        }
+    }
+
+    #[test]
+    fn solvers_match_brute_force_with_time_windows() {
+        let layout = Layout {
+            windows: true,
+            ..SINGLE
+        };
+        for objective in &objectives() {
+            let branch_bound = BranchBound {
+                objective: objective.clone(),
+                ..BranchBound::default()
+            };
+            let dynamic_program = DynamicProgram {
+                objective: objective.clone(),
+            };
+            for seed in 1..=12 {
+                let instance = instance(6, seed, layout);
+                assert_optimal(&branch_bound, &instance, objective, seed);
+                assert_optimal(&dynamic_program, &instance, objective, seed);
+            }
+        }
+    }
+
+    #[test]
+    fn solvers_match_brute_force_on_several_runways() {
+        let layout = Layout {
+            runways: 2,
+            windows: true,
+            ..SINGLE
+        };
+        let objective = Objective::default();
+        for seed in 1..=8 {
+            let instance = instance(5, seed, layout);
+            assert_optimal(&BranchBound::default(), &instance, &objective, seed);
+            assert_optimal(&DynamicProgram::default(), &instance, &objective, seed);
+        }
+    }
+
+    #[test]
+    fn dynamic_program_matches_brute_force_with_several_deice_pads() {
+        let layout = Layout {
+            deice_pads: 2,
+            windows: true,
+            ..SINGLE
+        };
+        let objective = Objective::default();
+        for seed in 1..=8 {
+            let instance = instance(5, seed, layout);
+            assert_optimal(&DynamicProgram::default(), &instance, &objective, seed);
+
+            // NOTE: Branch-and-bound only de-ices at the de-icing pad that becomes free the earliest, so it can only
+            //       ever be as good as brute force, and never claims to be optimal.
+            if let Ok(comparison) = compare_with_brute_force(&instance, &objective) {
+                let branch_bound = comparison.branch_bound.as_ref().unwrap();
+                assert!(!branch_bound.optimal, "seed {seed}");
+                assert!(comparison.branch_bound_cost >= Some(comparison.optimal_cost));
+            }
+        }
+    }
+
+    #[test]
+    fn solvers_match_brute_force_with_position_shifting() {
+        let objective = Objective::default();
+        for max_position_shift in [0, 1, 2] {
+            for seed in 1..=6 {
+                let mut instance = instance(6, seed, SINGLE);
+                instance.max_position_shift = Some(max_position_shift);
+                assert_optimal(&BranchBound::default(), &instance, &objective, seed);
+                assert_optimal(&DynamicProgram::default(), &instance, &objective, seed);
+            }
+        }
+    }
+
+    #[test]
+    fn windowed_aircraft_are_not_ordered_after_unwindowed_ones() {
+        let flights = (0..3)
+            .map(|idx| {
+                Flight::Arr(Arrival {
+                    earliest_time: start(),
+                    base_time: start(),
+                    window: (idx == 2).then_some(TimeWindow {
+                        earliest: start(),
+                        duration: MINUTE,
+                    }),
+                    runways: None,
+                    info: None,
+                })
+            })
+            .collect::<Vec<_>>();
+        let separations = (0..9)
+            .map(|idx| match idx % 4 {
+                0 => Duration::ZERO,
+                _ => MINUTE * 5,
+            })
+            .collect::<Vec<_>>();
+        let instance = Instance::new(
+            flights,
+            Separations::new(separations, 3).unwrap(),
+            MINUTE * 10,
+        )
+        .unwrap();
+
+        for objective in &objectives() {
+            let branch_bound = BranchBound {
+                objective: objective.clone(),
+                ..BranchBound::default()
+            };
+            let dynamic_program = DynamicProgram {
+                objective: objective.clone(),
+            };
+            assert_optimal(&branch_bound, &instance, objective, 0);
+            assert_optimal(&dynamic_program, &instance, objective, 0);
+        }
+    }
+
+    #[test]
+    fn brute_force_is_not_optimal_when_later_times_are_rewarded() {
+        let objective = Objective {
+            stability: Term::new(1, Penalty::Linear),
+            stability_shift: Shift::Time,
+            ..Objective::default()
+        };
+        let comparison = compare_with_brute_force(&instance(4, 1, SINGLE), &objective).unwrap();
+        assert!(!comparison.optimal.optimal);
+    }
+
+    #[test]
+    fn parallel_search_matches_sequential_search_with_rolling_horizon() {
+        let layout = Layout {
+            windows: true,
+            ..SINGLE
+        };
+        for objective in objectives() {
+            for seed in 1..=12 {
+                let instance = instance(7, seed, layout);
+                let solver = |threads| BranchBound {
+                    horizon: NonZeroUsize::new(3).map(RollingHorizon::flights),
+                    threads: NonZeroUsize::new(threads),
+                    objective: objective.clone(),
+                    ..BranchBound::default()
+                };
+                let sequential = solver(1)
+                    .solve(&instance)
+                    .map(|solution| solution.schedules);
+                for threads in [2, 4] {
+                    let parallel = solver(threads)
+                        .solve(&instance)
+                        .map(|solution| solution.schedules);
+                    assert_eq!(parallel, sequential, "seed {seed} with {threads} threads");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reaching_a_limit_with_rolling_horizon_sequences_every_aircraft() {
+        for seed in 1..=8 {
+            let instance = instance(8, seed, SINGLE);
+            let solution = BranchBound {
+                horizon: NonZeroUsize::new(3).map(RollingHorizon::flights),
+                node_limit: Some(50),
+                ..BranchBound::default()
+            }
+            .solve(&instance)
+            .unwrap();
+            assert_eq!(solution.schedules.len(), 8, "seed {seed}");
+            assert_eq!(solution.nodes, Some(50), "seed {seed}");
+            assert!(!solution.optimal, "seed {seed}");
+        }
+    }
+}
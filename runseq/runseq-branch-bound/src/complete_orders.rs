@@ -1,18 +1,36 @@
 use std::cmp::Ordering;
 
-use runseq_instance::{flight::Flight, Instance};
+use runseq_instance::{flight::Flight, runway::RunwayRelation, Instance};
 
-pub fn separation_identical_complete_orders(instance: &Instance) -> Vec<Vec<usize>> {
+use crate::objective::Objective;
+
+pub fn separation_identical_complete_orders(
+    instance: &Instance,
+    objective: &Objective,
+) -> Vec<Vec<usize>> {
     let mut sets = Vec::<Vec<_>>::with_capacity(instance.flights().len().min(1));
+    let fcfs_positions = instance
+        .max_position_shift
+        .map(|_| instance.fcfs_positions());
+    let fcfs_positions = fcfs_positions.as_deref();
 
     // Goes through all pairs of aircraft and checks if they are separation-identical.
     // If they are, they are put in the first set for which a complete order exists between all of them.
     'unclassified: for j in 0..instance.flights().len() {
+        // NOTE: Aircraft with pinned schedules are always sequenced before every other aircraft, so they are not part
+        //       of any set.
+        if instance
+            .pin(j)
+            .is_some_and(|pin| pin.as_schedule().is_some())
+        {
+            continue;
+        }
+
         'sets: for set in &mut sets {
             let separation_identical_complete_order = set.iter().copied().all(|i| {
                 are_separation_identical(i, j, instance)
-                    && (complete_order_exists(i, j, instance)
-                        || complete_order_exists(j, i, instance))
+                    && (complete_order_exists(i, j, instance, objective, fcfs_positions)
+                        || complete_order_exists(j, i, instance, objective, fcfs_positions))
             });
 
             if !separation_identical_complete_order {
@@ -28,7 +46,7 @@ pub fn separation_identical_complete_orders(instance: &Instance) -> Vec<Vec<usiz
     }
 
     // The aircraft in each set are sorted in ascending order according to their release times,
-    // base times, and latest times in their time windows.
+    // base times, latest times in their time windows, and positions in the reference sequence if it is penalised.
     // If two aircraft have the same values for all of these, the one with the lower index (which will
     // always be unique) is put first.
     for set in &mut sets {
@@ -40,6 +58,7 @@ pub fn separation_identical_complete_orders(instance: &Instance) -> Vec<Vec<usiz
                 .cmp(&other.release_time())
                 .then_with(|| flight.base_time().cmp(&other.base_time()))
                 .then_with(|| cmp_latest(flight, other))
+                .then_with(|| cmp_reference(flight_idx, other_idx, instance, objective))
                 .then_with(|| flight_idx.cmp(&other_idx))
         })
     }
@@ -48,6 +67,10 @@ pub fn separation_identical_complete_orders(instance: &Instance) -> Vec<Vec<usiz
 }
 
 fn are_separation_identical(i: usize, j: usize, instance: &Instance) -> bool {
+    if !have_identical_runways(i, j, instance) {
+        return false;
+    }
+
     (0..instance.flights().len())
         .filter(|&k| i != k && j != k)
         .all(|k| {
@@ -61,16 +84,120 @@ fn are_separation_identical(i: usize, j: usize, instance: &Instance) -> bool {
         })
 }
 
-fn complete_order_exists(flight_idx: usize, other_idx: usize, instance: &Instance) -> bool {
+fn have_identical_runways(i: usize, j: usize, instance: &Instance) -> bool {
+    let flight_i = &instance.flights()[i];
+    let flight_j = &instance.flights()[j];
+
+    // NOTE: Arrivals and departures on segregated runways are separated differently, even if their separations are
+    //       otherwise identical.
+    let segregated = instance
+        .runway_pairs()
+        .iter()
+        .any(|pair| pair.relation == RunwayRelation::Segregated);
+    let same_kind = matches!(
+        (flight_i, flight_j),
+        (Flight::Arr(_), Flight::Arr(_)) | (Flight::Dep(_), Flight::Dep(_))
+    );
+
+    flight_i.runways() == flight_j.runways() && (!segregated || same_kind)
+}
+
+fn complete_order_exists(
+    flight_idx: usize,
+    other_idx: usize,
+    instance: &Instance,
+    objective: &Objective,
+    fcfs_positions: Option<&[usize]>,
+) -> bool {
     let flight = &instance.flights()[flight_idx];
     let other = &instance.flights()[other_idx];
 
-    // NOTE: Complete orders cannot be inferred when one or both flights are subject to CTOTs.
+    // NOTE: Complete orders cannot be inferred when one or both flights are subject to CTOTs or have pinned de-icing
+    //       slots.
+    //       Pinned de-icing slots also block their de-icing pads for every other departure that needs to be de-iced,
+    //       which can delay such departures well past their release times.
+    let deice_pinned = instance.pinned().iter().any(|pin| pin.deice().is_some());
     has_no_ctot(flight)
         && has_no_ctot(other)
+        && instance.pin(flight_idx).is_none()
+        && instance.pin(other_idx).is_none()
+        && !(deice_pinned && (needs_deice(flight) || needs_deice(other)))
         && flight.release_time() <= other.release_time()
         && flight.base_time() <= other.base_time()
         && cmp_latest(flight, other).is_le()
+        && follows_reference(flight_idx, other_idx, instance, objective)
+        && follows_fcfs(flight_idx, other_idx, fcfs_positions)
+        && have_identical_ground_movements(flight, other)
+}
+
+// NOTE: Swapping two aircraft that are sequenced against their first-come-first-served order never moves either of them
+//       too far from its first-come-first-served position, so a complete order can only be inferred if it agrees with
+//       the first-come-first-served order.
+fn follows_fcfs(flight_idx: usize, other_idx: usize, fcfs_positions: Option<&[usize]>) -> bool {
+    fcfs_positions.is_none_or(|positions| positions[flight_idx] < positions[other_idx])
+}
+
+// NOTE: Swapping two aircraft can bring them closer to their schedules in the reference sequence, so a complete order
+//       can only be inferred if the reference sequence has them in the same order, or if neither of them is part of it.
+fn follows_reference(
+    flight_idx: usize,
+    other_idx: usize,
+    instance: &Instance,
+    objective: &Objective,
+) -> bool {
+    if objective.stability.weight == 0 {
+        return true;
+    }
+
+    let reference = instance.reference();
+    match (
+        instance.reference_position(flight_idx),
+        instance.reference_position(other_idx),
+    ) {
+        (None, None) => true,
+        (Some(position), Some(other_position)) => {
+            position < other_position
+                && reference[position].flight_time() <= reference[other_position].flight_time()
+        },
+        _ => false,
+    }
+}
+
+fn cmp_reference(
+    flight_idx: usize,
+    other_idx: usize,
+    instance: &Instance,
+    objective: &Objective,
+) -> Ordering {
+    if objective.stability.weight == 0 {
+        return Ordering::Equal;
+    }
+
+    instance
+        .reference_position(flight_idx)
+        .cmp(&instance.reference_position(other_idx))
+}
+
+fn needs_deice(flight: &Flight) -> bool {
+    flight.as_departure().is_some_and(|dep| dep.deice.is_some())
+}
+
+// NOTE: Departures have to be pushed back, de-iced, and taxied out before they can take off, which can delay them past
+//       their release times and make them hold at the runway.
+//       Swapping an arrival and a departure, or two departures that take different amounts of time to get to the
+//       runway or have different holdover times, can therefore change their costs even if their separations are
+//       identical.
+fn have_identical_ground_movements(flight: &Flight, other: &Flight) -> bool {
+    match (flight, other) {
+        (Flight::Arr(_), Flight::Arr(_)) => true,
+        (Flight::Dep(dep), Flight::Dep(other)) => {
+            dep.pushback_duration == other.pushback_duration
+                && dep.deice == other.deice
+                && dep.taxi_duration == other.taxi_duration
+                && dep.lineup_duration == other.lineup_duration
+        },
+        _ => false,
+    }
 }
 
 fn has_no_ctot(flight: &Flight) -> bool {
@@ -81,11 +208,12 @@ fn has_no_ctot(flight: &Flight) -> bool {
 }
 
 fn cmp_latest(flight: &Flight, other: &Flight) -> Ordering {
+    // NOTE: This must be a total order, since it is used to sort the aircraft in each complete-order set.
     match (flight.window(), other.window()) {
-        // The latest time of an aircraft does not matter if the aircraft being compared has no time window.
-        (_, None) => Ordering::Equal,
+        (None, None) => Ordering::Equal,
         // The latest time of an aircraft that has no time window is always greater than the latest time of any other aircraft.
         (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
         (Some(flight_window), Some(other_window)) => {
             flight_window.latest().cmp(&other_window.latest())
         },
@@ -1,42 +1,150 @@
 use std::{
     cmp::Ordering,
     iter::Sum,
-    ops::{Add, AddAssign, Sub, SubAssign},
+    ops::{Add, AddAssign},
     time::Duration,
 };
 
+use chrono::NaiveDateTime;
+
 use runseq_instance::{
-    flight::{Arrival, Departure, Flight},
+    flight::{Arrival, Departure, Flight, TimeWindow},
+    pin::Pin,
     schedule::{ArrivalSchedule, DepartureSchedule, Schedule},
     Instance,
 };
 
-use crate::search::BranchBoundState;
+use crate::{
+    objective::Objective,
+    search::{deice_pad_ends, separation_end, BranchBoundState, Deicing},
+    LowerBound,
+};
 
+/// The cost of scheduling one or more aircraft according to an [`Objective`], broken down by objective term.
+///
+/// Costs are ordered by their objective value, with ties broken by their runway hold penalty if it is not part of the
+/// objective value.
+/// Each component is the weighted penalty of its [`Objective`] term, rather than the deviation it penalises.
 #[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
 pub struct Cost {
-    pub delay: u64,
-    pub ctot_violation: u64,
-    pub runway_hold: u64,
+    delay: u64,
+    ctot_violation: u64,
+    ctot_early: u64,
+    runway_hold: u64,
+    stability: u64,
+    violation: u64,
+    objective: u64,
+    tiebreak: u64,
 }
 
 impl Cost {
-    pub const MAX: Self = Self {
+    pub(crate) const MAX: Self = Self {
         delay: u64::MAX,
         ctot_violation: 0,
+        ctot_early: 0,
         runway_hold: 0,
+        stability: 0,
+        violation: 0,
+        objective: u64::MAX,
+        tiebreak: 0,
     };
 
+    // NOTE: The tiebreak is maxed out so that sequences with exactly the given objective value still compare as better
+    //       than the upper bound, and are not pruned.
+    pub(crate) fn upper_bound(objective: u64) -> Self {
+        Self {
+            objective,
+            tiebreak: u64::MAX,
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn new(
+        delay: u64,
+        ctot_violation: u64,
+        ctot_early: u64,
+        runway_hold: u64,
+        stability: u64,
+        violation: u64,
+        runway_hold_in_objective: bool,
+    ) -> Self {
+        let penalties = [delay, ctot_violation, ctot_early, stability, violation]
+            .into_iter()
+            .fold(0, u64::saturating_add);
+        let (objective, tiebreak) = match runway_hold_in_objective {
+            true => (penalties.saturating_add(runway_hold), 0),
+            false => (penalties, runway_hold),
+        };
+        Self {
+            delay,
+            ctot_violation,
+            ctot_early,
+            runway_hold,
+            stability,
+            violation,
+            objective,
+            tiebreak,
+        }
+    }
+
+    // NOTE: Costs are only ever subtracted from sums they are part of, so this only saturates if the sum did as well.
+    pub(crate) fn saturating_sub(self, rhs: Self) -> Self {
+        Self {
+            delay: self.delay.saturating_sub(rhs.delay),
+            ctot_violation: self.ctot_violation.saturating_sub(rhs.ctot_violation),
+            ctot_early: self.ctot_early.saturating_sub(rhs.ctot_early),
+            runway_hold: self.runway_hold.saturating_sub(rhs.runway_hold),
+            stability: self.stability.saturating_sub(rhs.stability),
+            violation: self.violation.saturating_sub(rhs.violation),
+            objective: self.objective.saturating_sub(rhs.objective),
+            tiebreak: self.tiebreak.saturating_sub(rhs.tiebreak),
+        }
+    }
+
+    /// Returns the objective value of the cost.
+    ///
+    /// This is the sum of the components that are part of the objective value, which excludes the runway hold penalty
+    /// unless [`Objective::runway_hold_in_objective`] is set.
     pub fn as_u64(&self) -> u64 {
-        self.delay + self.ctot_violation
+        self.objective
+    }
+
+    /// Returns the penalty for delay.
+    pub fn delay(&self) -> u64 {
+        self.delay
+    }
+
+    /// Returns the penalty for taking off after the end of a Calculated Take-Off Time (CTOT) slot.
+    pub fn ctot_violation(&self) -> u64 {
+        self.ctot_violation
+    }
+
+    /// Returns the penalty for taking off before a target CTOT.
+    pub fn ctot_early(&self) -> u64 {
+        self.ctot_early
+    }
+
+    /// Returns the penalty for holding at the runway after de-icing.
+    pub fn runway_hold(&self) -> u64 {
+        self.runway_hold
+    }
+
+    /// Returns the penalty for deviating from a reference sequence.
+    pub fn stability(&self) -> u64 {
+        self.stability
+    }
+
+    /// Returns the penalty for violating soft time windows, Holdover Times (HOTs), or maximum runway hold durations.
+    pub fn violation(&self) -> u64 {
+        self.violation
     }
 }
 
 impl Ord for Cost {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.as_u64()
-            .cmp(&other.as_u64())
-            .then_with(|| self.runway_hold.cmp(&other.runway_hold))
+        self.objective
+            .cmp(&other.objective)
+            .then_with(|| self.tiebreak.cmp(&other.tiebreak))
     }
 }
 
@@ -51,38 +159,28 @@ impl Add for Cost {
 
     fn add(self, rhs: Self) -> Self::Output {
         Self {
-            delay: self.delay + rhs.delay,
-            ctot_violation: self.ctot_violation + rhs.ctot_violation,
-            runway_hold: self.runway_hold + rhs.runway_hold,
+            delay: self.delay.saturating_add(rhs.delay),
+            ctot_violation: self.ctot_violation.saturating_add(rhs.ctot_violation),
+            ctot_early: self.ctot_early.saturating_add(rhs.ctot_early),
+            runway_hold: self.runway_hold.saturating_add(rhs.runway_hold),
+            stability: self.stability.saturating_add(rhs.stability),
+            violation: self.violation.saturating_add(rhs.violation),
+            objective: self.objective.saturating_add(rhs.objective),
+            tiebreak: self.tiebreak.saturating_add(rhs.tiebreak),
         }
     }
 }
 
 impl AddAssign for Cost {
     fn add_assign(&mut self, rhs: Self) {
-        self.delay += rhs.delay;
-        self.ctot_violation += rhs.ctot_violation;
-        self.runway_hold += rhs.runway_hold;
-    }
-}
-
-impl Sub for Cost {
-    type Output = Self;
-
-    fn sub(self, rhs: Self) -> Self::Output {
-        Self {
-            delay: self.delay - rhs.delay,
-            ctot_violation: self.ctot_violation - rhs.ctot_violation,
-            runway_hold: self.runway_hold - rhs.runway_hold,
-        }
-    }
-}
-
-impl SubAssign for Cost {
-    fn sub_assign(&mut self, rhs: Self) {
-        self.delay -= rhs.delay;
-        self.ctot_violation -= rhs.ctot_violation;
-        self.runway_hold -= rhs.runway_hold;
+        self.delay = self.delay.saturating_add(rhs.delay);
+        self.ctot_violation = self.ctot_violation.saturating_add(rhs.ctot_violation);
+        self.ctot_early = self.ctot_early.saturating_add(rhs.ctot_early);
+        self.runway_hold = self.runway_hold.saturating_add(rhs.runway_hold);
+        self.stability = self.stability.saturating_add(rhs.stability);
+        self.violation = self.violation.saturating_add(rhs.violation);
+        self.objective = self.objective.saturating_add(rhs.objective);
+        self.tiebreak = self.tiebreak.saturating_add(rhs.tiebreak);
     }
 }
 
@@ -95,85 +193,249 @@ impl Sum for Cost {
     }
 }
 
-pub fn arrival_cost(sched: &ArrivalSchedule, arr: &Arrival) -> Cost {
-    let delay = (sched.landing - arr.base_time)
-        .num_seconds()
-        .unsigned_abs()
-        .pow(2);
+/// The cost of a runway sequence, broken down by aircraft.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct CostBreakdown {
+    /// The cost of each schedule, in the order they are sequenced in.
+    pub schedules: Vec<ScheduleCost>,
+    /// The total cost of the runway sequence.
+    pub total: Cost,
+}
 
-    Cost {
-        delay,
-        ctot_violation: 0,
-        runway_hold: 0,
+impl CostBreakdown {
+    pub(crate) fn new(solution: &[Schedule], instance: &Instance, objective: &Objective) -> Self {
+        let reference_positions = instance.reference_positions();
+        let schedules = solution
+            .iter()
+            .enumerate()
+            .map(|(position, sched)| ScheduleCost {
+                flight_index: sched.flight_index(),
+                cost: schedule_cost(sched, position, instance, objective, &reference_positions),
+            })
+            .collect::<Vec<_>>();
+        let total = schedules.iter().map(|sched| sched.cost).sum();
+
+        Self { schedules, total }
+    }
+}
+
+/// The cost of scheduling a single aircraft.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct ScheduleCost {
+    /// The index of the aircraft.
+    pub flight_index: usize,
+    /// The cost of the aircraft's schedule.
+    pub cost: Cost,
+}
+
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Deviations {
+    pub delay: Duration,
+    pub ctot_late: Duration,
+    pub ctot_early: Duration,
+    pub runway_hold: Duration,
+    pub time_shift: Duration,
+    pub position_shift: u64,
+    pub window_late: Duration,
+    pub hot_excess: Duration,
+    pub runway_hold_excess: Duration,
+}
+
+pub fn arrival_cost(
+    sched: &ArrivalSchedule,
+    position: usize,
+    arr: &Arrival,
+    instance: &Instance,
+    objective: &Objective,
+    reference_positions: &[Option<usize>],
+) -> Cost {
+    objective.cost(&stability_deviations(
+        arrival_deviations(sched, arr),
+        sched.flight_index,
+        sched.landing,
+        position,
+        instance,
+        objective,
+        reference_positions,
+    ))
+}
+
+pub fn departure_cost(
+    sched: &DepartureSchedule,
+    position: usize,
+    dep: &Departure,
+    instance: &Instance,
+    objective: &Objective,
+    reference_positions: &[Option<usize>],
+) -> Cost {
+    objective.cost(&stability_deviations(
+        departure_deviations(sched, dep, instance),
+        sched.flight_index,
+        sched.takeoff,
+        position,
+        instance,
+        objective,
+        reference_positions,
+    ))
+}
+
+fn arrival_deviations(sched: &ArrivalSchedule, arr: &Arrival) -> Deviations {
+    Deviations {
+        delay: seconds(sched.landing - arr.base_time),
+        window_late: window_late(sched.landing, arr.window.as_ref()),
+        ..Deviations::default()
     }
 }
 
-pub fn departure_cost(sched: &DepartureSchedule, dep: &Departure) -> Cost {
-    let delay = (sched.takeoff - dep.base_time)
-        .num_seconds()
-        .unsigned_abs()
-        .pow(2);
+fn departure_deviations(
+    sched: &DepartureSchedule,
+    dep: &Departure,
+    instance: &Instance,
+) -> Deviations {
+    let delay = seconds(sched.takeoff - dep.base_time);
+
+    let ctot_late = match &dep.ctot {
+        None => Duration::ZERO,
+        Some(ctot) if ctot.as_range().contains(&sched.takeoff) => Duration::ZERO,
+        Some(ctot) => seconds(sched.takeoff - ctot.latest()),
+    };
 
-    let ctot_violation = match &dep.ctot {
-        None => 0,
-        Some(ctot) if ctot.as_range().contains(&sched.takeoff) => 0,
-        Some(ctot) => (sched.takeoff - ctot.latest())
-            .num_seconds()
-            .unsigned_abs()
-            .pow(2),
+    let ctot_early = match &dep.ctot {
+        Some(ctot) if sched.takeoff < ctot.target => seconds(ctot.target - sched.takeoff),
+        _ => Duration::ZERO,
     };
 
-    let runway_hold = match sched.deice {
-        None => 0,
+    let (runway_hold, hot_excess) = match sched.deice {
+        None => (Duration::ZERO, Duration::ZERO),
         Some(deice) => {
             // The runway hold time can be calculated as the difference between the departure's latest possible
             // de-icing time (according to its scheduled take-off time) and its actual de-icing time.
-            let deice_duration = dep.deice.as_ref().unwrap().duration;
+            let params = dep.deice.as_ref().unwrap();
+            let deice_end = deice + instance.deice_duration(params, sched.deice_pad);
             let runway_hold =
-                sched.takeoff - dep.lineup_duration - dep.taxi_duration - deice_duration - deice;
-            runway_hold.num_seconds().unsigned_abs().pow(2)
+                seconds(sched.takeoff - dep.lineup_duration - dep.taxi_duration - deice_end);
+            let hot_excess = seconds(sched.takeoff - deice_end).saturating_sub(params.hot);
+            (runway_hold, hot_excess)
         },
     };
 
-    Cost {
+    Deviations {
         delay,
-        ctot_violation,
+        ctot_late,
+        ctot_early,
         runway_hold,
+        window_late: window_late(sched.takeoff, dep.window.as_ref()),
+        hot_excess,
+        runway_hold_excess: runway_hold.saturating_sub(instance.max_runway_hold_duration),
+        ..Deviations::default()
     }
 }
 
-pub fn schedule_cost(sched: &Schedule, instance: &Instance) -> Cost {
+fn schedule_deviations(sched: &Schedule, instance: &Instance) -> Deviations {
     match sched {
         Schedule::Arr(sched) => {
             let arr = instance.flights()[sched.flight_index].as_arrival().unwrap();
-            arrival_cost(sched, arr)
+            arrival_deviations(sched, arr)
         },
         Schedule::Dep(sched) => {
             let dep = instance.flights()[sched.flight_index]
                 .as_departure()
                 .unwrap();
-            departure_cost(sched, dep)
+            departure_deviations(sched, dep, instance)
         },
     }
 }
 
+pub fn schedule_cost(
+    sched: &Schedule,
+    position: usize,
+    instance: &Instance,
+    objective: &Objective,
+    reference_positions: &[Option<usize>],
+) -> Cost {
+    objective.cost(&stability_deviations(
+        schedule_deviations(sched, instance),
+        sched.flight_index(),
+        sched.flight_time(),
+        position,
+        instance,
+        objective,
+        reference_positions,
+    ))
+}
+
+// Adds how far an aircraft at the given position in the sequence deviates from the reference sequence of the instance,
+// looking up its position in the reference sequence in a table indexed by aircraft index
+// NOTE: This is left out of the lower bounds, which is always admissible since the deviations are never negative.
+fn stability_deviations(
+    deviations: Deviations,
+    flight_idx: usize,
+    flight_time: NaiveDateTime,
+    position: usize,
+    instance: &Instance,
+    objective: &Objective,
+    reference_positions: &[Option<usize>],
+) -> Deviations {
+    if objective.stability.weight == 0 {
+        return deviations;
+    }
+    let Some(reference_position) = reference_positions[flight_idx] else {
+        return deviations;
+    };
+    let reference = &instance.reference()[reference_position];
+
+    Deviations {
+        time_shift: seconds(flight_time - reference.flight_time()),
+        position_shift: position.abs_diff(reference_position) as u64,
+        ..deviations
+    }
+}
+
 /// Calculates the objective value of a runway sequence.
 ///
 /// # Panics
 ///
 /// This function will panic if the number of aircraft in the sequence does not match the number of aircraft in the instance,
 /// which can happen if the given runway sequence was not produced by solving the given instance.
+/// Use [`verify`](runseq_instance::verify::verify) to check a runway sequence against an instance beforehand.
+///
+/// The sequence is evaluated using the default [`Objective`].
+/// Use [`Objective::solution_cost`] to evaluate it using a different objective.
 pub fn solution_cost(solution: &[Schedule], instance: &Instance) -> Cost {
-    solution
-        .iter()
-        .map(|sched| schedule_cost(sched, instance))
-        .sum()
+    Objective::default().solution_cost(solution, instance)
+}
+
+/// Calculates the cost of each aircraft in a runway sequence, along with the total cost of the sequence.
+///
+/// # Panics
+///
+/// This function will panic if any of the schedules refer to aircraft that are not in the instance.
+///
+/// The sequence is evaluated using the default [`Objective`].
+/// Use [`Objective::cost_breakdown`] to evaluate it using a different objective.
+pub fn cost_breakdown(solution: &[Schedule], instance: &Instance) -> CostBreakdown {
+    Objective::default().cost_breakdown(solution, instance)
 }
 
 pub fn estimated_remaining_cost(
     instance: &Instance,
     state: &BranchBoundState,
     last_sched: &Schedule,
+    objective: &Objective,
+    lower_bound: LowerBound,
+    deicing: Deicing<'_>,
+) -> Cost {
+    match lower_bound {
+        LowerBound::Simple => simple_remaining_cost(instance, state, last_sched, objective),
+        LowerBound::Tight => tight_remaining_cost(instance, state, objective, deicing),
+    }
+}
+
+fn simple_remaining_cost(
+    instance: &Instance,
+    state: &BranchBoundState,
+    last_sched: &Schedule,
+    objective: &Objective,
 ) -> Cost {
     // NOTE: A minimum separation of zero seconds is used as this seems to provide better lower bounds.
     let min_sep = Duration::from_secs(0);
@@ -186,6 +448,8 @@ pub fn estimated_remaining_cost(
     // in a quick and cheap way.
     // Additionally, this method ensures that the lower bound will never exceed the actual cost, since when actually
     // scheduling aircraft they will never be scheduled to land or take-off at the same time.
+    // This holds for every objective, since all penalties grow with delay, lateness, and runway hold - except for the
+    // penalty for taking off early in a CTOT slot, which shrinks with later take-off times and is thus left out.
     state
         .complete_order_sets
         .iter()
@@ -200,11 +464,14 @@ pub fn estimated_remaining_cost(
                         Flight::Arr(arr) => Schedule::Arr(ArrivalSchedule {
                             flight_index: flight_idx,
                             landing: arr.release_time().max(last_sched.flight_time() + min_sep),
+                            runway: last_sched.runway(),
                         }),
                         Flight::Dep(dep) => Schedule::Dep(DepartureSchedule {
                             flight_index: flight_idx,
                             takeoff: dep.release_time().max(last_sched.flight_time() + min_sep),
                             deice: None,
+                            deice_pad: 0,
+                            runway: last_sched.runway(),
                         }),
                     };
                     *last_sched = sched.clone();
@@ -212,7 +479,152 @@ pub fn estimated_remaining_cost(
                 })
         })
         .flat_map(|remaining_solution| {
-            remaining_solution.map(|sched| schedule_cost(&sched, instance))
+            remaining_solution.map(|sched| {
+                let deviations = Deviations {
+                    ctot_early: Duration::ZERO,
+                    ..schedule_deviations(&sched, instance)
+                };
+                objective.cost(&deviations)
+            })
+        })
+        .sum()
+}
+
+fn tight_remaining_cost(
+    instance: &Instance,
+    state: &BranchBoundState,
+    objective: &Objective,
+    deicing: Deicing<'_>,
+) -> Cost {
+    // Under integrated de-icing, a departure can only be de-iced once one of the de-icing pads becomes free.
+    // If any pad has not been used yet, then it is free from the start and de-icing does not bound the take-off time.
+    let pad_ends = match deicing {
+        Deicing::Integrated => deice_pad_ends(instance, state)
+            .into_iter()
+            .collect::<Option<Vec<_>>>(),
+        Deicing::Decomposed(_) => None,
+    };
+
+    // To calculate the estimated remaining cost, every remaining aircraft is again scheduled as soon as possible.
+    // The first aircraft in each set of complete-ordered aircraft must be separated from every aircraft in the current
+    // solution, and every following aircraft must be separated from the one before it in the same set, using the
+    // smallest separation over all runways they are allowed to use.
+    // Departures must additionally finish de-icing, taxi out, and line up before taking off.
+    // Every time calculated this way is no later than the actual time the aircraft can be scheduled at, so the lower
+    // bound still never exceeds the actual cost.
+    state
+        .complete_order_sets
+        .iter()
+        .zip(&state.next_in_complete_order_sets)
+        .map(|(complete_order_set, &next_in_set_idx)| &complete_order_set[next_in_set_idx..])
+        .flat_map(|remaining_solution| {
+            remaining_solution.iter().scan(
+                None,
+                |last: &mut Option<(usize, NaiveDateTime)>, &flight_idx| {
+                    let flight = &instance.flights()[flight_idx];
+                    let sep_end = match *last {
+                        None => instance
+                            .allowed_runways(flight_idx)
+                            .map(|runway| separation_end(instance, state, flight_idx, runway))
+                            .min(),
+                        Some((last_idx, last_time)) => {
+                            Some(last_time + min_separation(instance, last_idx, flight_idx))
+                        },
+                    };
+                    let mut time = flight
+                        .release_time()
+                        .max(sep_end.unwrap_or(NaiveDateTime::MIN));
+
+                    if let Flight::Dep(dep) = flight {
+                        let pinned_deice = instance.pin(flight_idx).and_then(Pin::deice);
+                        let deice_end =
+                            dep.deice
+                                .as_ref()
+                                .and_then(|deice| match (deicing, pinned_deice) {
+                                    // NOTE: A departure with a pinned de-icing slot is de-iced at exactly that
+                                    //       time, even though its own slot counts towards when its de-icing pad
+                                    //       becomes free.
+                                    (Deicing::Integrated, Some((deice_time, pad))) => {
+                                        Some(deice_time + instance.deice_duration(deice, pad))
+                                    },
+                                    (Deicing::Integrated, None) => {
+                                        pad_ends.as_ref().and_then(|pad_ends| {
+                                            pad_ends
+                                                .iter()
+                                                .enumerate()
+                                                .map(|(pad, &pad_end)| {
+                                                    pad_end + instance.deice_duration(deice, pad)
+                                                })
+                                                .min()
+                                        })
+                                    },
+                                    (Deicing::Decomposed(deice_queue), _) => {
+                                        deice_queue.get(&flight_idx).map(|&(deice_time, pad)| {
+                                            deice_time + instance.deice_duration(deice, pad)
+                                        })
+                                    },
+                                });
+                        if let Some(deice_end) = deice_end {
+                            time = time.max(deice_end + dep.taxi_duration + dep.lineup_duration);
+                        }
+                    }
+
+                    *last = Some((flight_idx, time));
+                    Some(remaining_schedule(flight, flight_idx, time))
+                },
+            )
+        })
+        .map(|sched| {
+            let deviations = Deviations {
+                ctot_early: Duration::ZERO,
+                ..schedule_deviations(&sched, instance)
+            };
+            objective.cost(&deviations)
         })
         .sum()
 }
+
+// Finds the smallest separation between two aircraft over all runways they are allowed to use
+fn min_separation(instance: &Instance, from: usize, to: usize) -> Duration {
+    instance
+        .allowed_runways(from)
+        .flat_map(|from_rwy| {
+            instance.allowed_runways(to).map(move |to_rwy| {
+                instance
+                    .separation(from, from_rwy, to, to_rwy)
+                    .unwrap_or(Duration::ZERO)
+            })
+        })
+        .min()
+        .unwrap_or(Duration::ZERO)
+}
+
+fn remaining_schedule(flight: &Flight, flight_index: usize, time: NaiveDateTime) -> Schedule {
+    match flight {
+        Flight::Arr(_) => Schedule::Arr(ArrivalSchedule {
+            flight_index,
+            landing: time,
+            runway: 0,
+        }),
+        Flight::Dep(_) => Schedule::Dep(DepartureSchedule {
+            flight_index,
+            takeoff: time,
+            deice: None,
+            deice_pad: 0,
+            runway: 0,
+        }),
+    }
+}
+
+// The time an aircraft lands or takes off after the end of its time window, which is only non-zero if time windows are
+// soft constraints
+fn window_late(time: NaiveDateTime, window: Option<&TimeWindow>) -> Duration {
+    match window {
+        Some(window) if time > window.latest() => seconds(time - window.latest()),
+        _ => Duration::ZERO,
+    }
+}
+
+fn seconds(duration: chrono::Duration) -> Duration {
+    Duration::from_secs(duration.num_seconds().unsigned_abs())
+}
@@ -0,0 +1,150 @@
+use chrono::NaiveDateTime;
+
+use runseq_instance::{
+    schedule::Schedule,
+    solve::{Constraint, InfeasibleFlight, SolveError},
+    Instance,
+};
+
+use crate::{
+    cost::schedule_cost,
+    integrated::expand,
+    objective::Objective,
+    search::{BranchBoundState, Node},
+};
+
+/// Schedules aircraft in a fixed order, assigning each aircraft the cheapest landing or take-off time, runway, and
+/// de-icing time that is feasible after the aircraft before it.
+///
+/// Departures are de-iced as early as possible among their cheapest options, so that de-icing pads become free as
+/// early as possible for the departures after them.
+/// Once every aircraft is scheduled, de-icing times are then delayed as much as possible without delaying any take-off
+/// or the de-icing of any other departure, which minimises the time spent holding at the runway.
+///
+/// This uses the same timing logic as [`BranchBound`](crate::BranchBound) with
+/// [`DeiceStrategy::Integrated`](crate::DeiceStrategy::Integrated), but only explores a single branch of the search
+/// tree.
+/// The order does not need to contain every aircraft in the instance, in which case only the given aircraft are
+/// scheduled.
+/// Aircraft with [pinned](runseq_instance::pin::Pin) schedules are always scheduled first and in the order of their
+/// landing or take-off times, whether or not they are part of the order.
+///
+/// Returns a [`SolveError`] covering the positions that could not be filled if an aircraft cannot be feasibly
+/// scheduled after the aircraft before it, or if it is further from its first-come-first-served position than allowed
+/// by [`Instance::max_position_shift`].
+///
+/// # Panics
+///
+/// This function will panic if the order refers to aircraft that are not in the instance.
+pub fn decode(
+    order: &[usize],
+    instance: &Instance,
+    objective: &Objective,
+) -> Result<Vec<Schedule>, SolveError> {
+    let mut state = BranchBoundState::without_complete_orders(instance, objective);
+
+    let order = order
+        .iter()
+        .copied()
+        .filter(|&flight_idx| {
+            instance
+                .pin(flight_idx)
+                .is_none_or(|pin| pin.as_schedule().is_none())
+        })
+        .collect::<Vec<_>>();
+    let end = state.current_solution.len() + order.len();
+
+    for flight_idx in order {
+        let depth = state.current_solution.len();
+        let flight = &instance.flights()[flight_idx];
+
+        let infeasible = |constraint| SolveError::Infeasible {
+            window: depth..end,
+            flights: vec![InfeasibleFlight {
+                flight_index: flight_idx,
+                constraint,
+            }],
+        };
+
+        if state
+            .position_shift
+            .as_ref()
+            .is_some_and(|shift| !shift.within(flight_idx, depth))
+        {
+            return Err(infeasible(Constraint::PositionShift));
+        }
+
+        // PANICS: Expanding an aircraft never succeeds without at least one schedule.
+        let (sched, cost) = expand(flight, flight_idx, instance, &state)
+            .map_err(infeasible)?
+            .into_iter()
+            .map(|sched| {
+                let cost = schedule_cost(
+                    &sched,
+                    depth,
+                    instance,
+                    objective,
+                    &state.reference_positions,
+                );
+                (sched, cost)
+            })
+            .min_by_key(|(sched, cost)| (cost.as_u64(), deice_time(sched)))
+            .unwrap();
+
+        state.current_solution.push(Node {
+            sched,
+            depth,
+            complete_order_idx: 0,
+            cost,
+        });
+    }
+
+    let mut solution = state
+        .current_solution
+        .into_iter()
+        .map(|node| node.sched)
+        .collect::<Vec<_>>();
+    delay_deicing(&mut solution, instance);
+    Ok(solution)
+}
+
+fn deice_time(sched: &Schedule) -> Option<NaiveDateTime> {
+    sched.as_departure().and_then(|sched| sched.deice)
+}
+
+// Delays every de-icing time until the departure can taxi out and line up without holding at the runway, or until the
+// next departure on the same de-icing pad starts de-icing, whichever comes first
+fn delay_deicing(solution: &mut [Schedule], instance: &Instance) {
+    // NOTE: Departures are de-iced in the order they are sequenced in on each de-icing pad, so the solution is walked
+    //       backwards to know when the next departure on each pad starts de-icing.
+    let mut next_deices = vec![None; instance.deice_pads().len()];
+    for sched in solution.iter_mut().rev() {
+        let Schedule::Dep(sched) = sched else {
+            continue;
+        };
+        let Some(deice) = sched.deice else {
+            continue;
+        };
+
+        // NOTE: Pinned de-icing times can never be delayed.
+        if instance.pin(sched.flight_index).is_some() {
+            next_deices[sched.deice_pad] = Some(deice);
+            continue;
+        }
+
+        // PANICS: Only departures that need to be de-iced are ever given a de-icing time.
+        let dep = instance.flights()[sched.flight_index]
+            .as_departure()
+            .unwrap();
+        let deice_dur = instance.deice_duration(dep.deice.as_ref().unwrap(), sched.deice_pad);
+
+        let mut latest_deice = sched.takeoff - dep.lineup_duration - dep.taxi_duration - deice_dur;
+        if let Some(next_deice) = next_deices[sched.deice_pad] {
+            latest_deice = latest_deice.min(next_deice - deice_dur);
+        }
+
+        let deice = deice.max(latest_deice);
+        sched.deice = Some(deice);
+        next_deices[sched.deice_pad] = Some(deice);
+    }
+}
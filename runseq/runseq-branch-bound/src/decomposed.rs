@@ -1,94 +1,56 @@
-use std::{cmp::Ordering, collections::HashMap, num::NonZeroUsize};
+use std::{cmp::Ordering, collections::HashMap};
 
 use chrono::NaiveDateTime;
 
-use either::{Left, Right};
-
 use runseq_instance::{
     flight::{Arrival, Deice, Departure, Flight},
     schedule::{ArrivalSchedule, DepartureSchedule, Schedule},
+    solve::{Constraint, SolveError},
     Instance,
 };
 
-use crate::search::{branch_bound, within_window, BranchBoundState};
+use crate::{
+    rolling::rolling_horizon,
+    search::{
+        branch_bound,
+        deice_pad_ends,
+        feasible,
+        separation_end,
+        within_window,
+        BranchBoundState,
+        Deicing,
+        SearchOptions,
+    },
+    RollingHorizon,
+};
 
 pub fn branch_bound_rolling<F>(
     instance: &Instance,
-    horizon: Option<NonZeroUsize>,
+    horizon: Option<&RollingHorizon>,
+    options: &SearchOptions<'_>,
     mut sorter: F,
-) -> Option<Vec<Schedule>>
+) -> Result<Vec<Schedule>, SolveError>
 where
     F: FnMut(&Departure, &Departure) -> Ordering,
 {
-    let flight_count = instance.flights().len();
-
-    let mut state = BranchBoundState::new(instance);
-
-    // Generate de-icing queue for the first run
+    let mut state = BranchBoundState::new(instance, options.objective);
     let mut deice_queue = HashMap::new();
-    generate_deice_queue(instance, &state, &mut deice_queue, &mut sorter);
-
-    let end = horizon
-        .map(usize::from)
-        .unwrap_or(flight_count)
-        .min(flight_count);
-
-    let mut nodes = Vec::with_capacity(flight_count);
-
-    // Perform branch-and-bound for the first window
-    branch_bound(
-        instance,
-        &mut state,
-        &mut nodes,
-        &mut |flight, flight_index, instance, state| {
-            expand(flight, flight_index, instance, state, &deice_queue)
-        },
-        0..end,
-    );
-
-    // Perform branch-and-bound for the remaining windows
-    let windows = (1..)
-        .zip(end + 1..=flight_count)
-        .map(|(start, end)| start..end);
-    for window in windows {
-        // Ignore all scheduled aircraft except for the first one
-        let fixed = state.best_solution.drain(..).next()?;
-        let fixed_idx = fixed.sched.flight_index();
-
-        // Save the first aircraft to the current solution
-        state.current_solution.push(fixed);
-
-        // Remove that aircraft from the sets of complete orders
-        state.next_in_complete_order_sets.fill(0);
-        for set in &mut state.complete_order_sets {
-            set.retain(|&flight_idx| flight_idx != fixed_idx);
-        }
 
-        // Re-generate de-icing queue
-        generate_deice_queue(instance, &state, &mut deice_queue, &mut sorter);
+    rolling_horizon(instance, horizon, &mut state, |state, window| {
+        // Re-generate the de-icing queue for the aircraft that have not been fixed yet
+        generate_deice_queue(instance, state, &mut deice_queue, &mut sorter);
 
-        // Perform branch-and-bound for the current window
         branch_bound(
             instance,
-            &mut state,
-            &mut nodes,
-            &mut |flight, flight_index, instance, state| {
+            state,
+            &|flight, flight_index, instance, state| {
                 expand(flight, flight_index, instance, state, &deice_queue)
             },
+            Deicing::Decomposed(&deice_queue),
             window,
-        );
-    }
-
-    // Ensure that a feasible last solution is produced
-    let last_best_solution = (!state.best_solution.is_empty()).then_some(state.best_solution)?;
-
-    let solution = state
-        .current_solution
-        .into_iter()
-        .chain(last_best_solution)
-        .map(|node| node.sched)
-        .collect();
-    Some(solution)
+            options,
+        )
+    })
 }
 
 fn expand(
@@ -96,16 +58,19 @@ fn expand(
     flight_idx: usize,
     instance: &Instance,
     state: &BranchBoundState,
-    deice_queue: &HashMap<usize, NaiveDateTime>,
-) -> impl Iterator<Item = Schedule> {
-    match flight {
-        Flight::Arr(arr) => {
-            Left(expand_arrival(arr, flight_idx, instance, state).map(Schedule::Arr))
-        },
-        Flight::Dep(dep) => Right(
-            expand_departure(dep, flight_idx, instance, state, deice_queue).map(Schedule::Dep),
-        ),
-    }
+    deice_queue: &HashMap<usize, (NaiveDateTime, usize)>,
+) -> Result<Vec<Schedule>, Constraint> {
+    let scheds = match flight {
+        Flight::Arr(arr) => expand_arrival(arr, flight_idx, instance, state)?
+            .into_iter()
+            .map(Schedule::Arr)
+            .collect(),
+        Flight::Dep(dep) => expand_departure(dep, flight_idx, instance, state, deice_queue)?
+            .into_iter()
+            .map(Schedule::Dep)
+            .collect(),
+    };
+    Ok(scheds)
 }
 
 fn expand_arrival(
@@ -113,28 +78,24 @@ fn expand_arrival(
     flight_index: usize,
     instance: &Instance,
     state: &BranchBoundState,
-) -> impl Iterator<Item = ArrivalSchedule> {
-    // Find the time when all separation requirements with already scheduled aircraft are satisfied
-    let sep_end = state
-        .current_solution
-        .iter()
-        .rev()
-        .map(|node| {
-            let sep = instance.separations()[(node.sched.flight_index(), flight_index)];
-            node.sched.flight_time() + sep
-        })
-        .max()
-        .unwrap_or(NaiveDateTime::MIN);
-
-    let landing = arr.release_time().max(sep_end);
-
-    // Ensure that the scheduled landing time respects all constraints
-    within_window(landing, arr.window.as_ref())
-        .then_some(ArrivalSchedule {
-            flight_index,
-            landing,
-        })
-        .into_iter()
+) -> Result<Vec<ArrivalSchedule>, Constraint> {
+    feasible(instance.allowed_runways(flight_index).map(|runway| {
+        // Find the time when all separation requirements with already scheduled aircraft are satisfied
+        let sep_end = separation_end(instance, state, flight_index, runway);
+
+        let landing = arr.release_time().max(sep_end);
+
+        // Ensure that the scheduled landing time respects all constraints
+        within_window(landing, arr.window.as_ref(), state.soft)
+            .then(|| {
+                vec![ArrivalSchedule {
+                    flight_index,
+                    landing,
+                    runway,
+                }]
+            })
+            .ok_or(Constraint::Window)
+    }))
 }
 
 fn expand_departure(
@@ -142,17 +103,12 @@ fn expand_departure(
     flight_index: usize,
     instance: &Instance,
     state: &BranchBoundState,
-    deice_queue: &HashMap<usize, NaiveDateTime>,
-) -> impl Iterator<Item = DepartureSchedule> {
+    deice_queue: &HashMap<usize, (NaiveDateTime, usize)>,
+) -> Result<Vec<DepartureSchedule>, Constraint> {
     match &dep.deice {
-        None => {
-            let scheds = expand_direct_departure(dep, flight_index, instance, state);
-            Left(scheds)
-        },
+        None => expand_direct_departure(dep, flight_index, instance, state),
         Some(deice) => {
-            let scheds =
-                expand_deiced_departure(dep, flight_index, deice, instance, state, deice_queue);
-            Right(scheds)
+            expand_deiced_departure(dep, flight_index, deice, instance, state, deice_queue)
         },
     }
 }
@@ -162,29 +118,26 @@ fn expand_direct_departure(
     flight_index: usize,
     instance: &Instance,
     state: &BranchBoundState,
-) -> impl Iterator<Item = DepartureSchedule> {
-    // Find the time when all separation requirements with already scheduled aircraft are satisfied
-    let sep_end = state
-        .current_solution
-        .iter()
-        .rev()
-        .map(|node| {
-            let sep = instance.separations()[(node.sched.flight_index(), flight_index)];
-            node.sched.flight_time() + sep
-        })
-        .max()
-        .unwrap_or(NaiveDateTime::MIN);
-
-    let takeoff = dep.release_time().max(sep_end);
-
-    // Ensure that the scheduled take-off time respects all constraints
-    within_window(takeoff, dep.window.as_ref())
-        .then_some(DepartureSchedule {
-            flight_index,
-            deice: None,
-            takeoff,
-        })
-        .into_iter()
+) -> Result<Vec<DepartureSchedule>, Constraint> {
+    feasible(instance.allowed_runways(flight_index).map(|runway| {
+        // Find the time when all separation requirements with already scheduled aircraft are satisfied
+        let sep_end = separation_end(instance, state, flight_index, runway);
+
+        let takeoff = dep.release_time().max(sep_end);
+
+        // Ensure that the scheduled take-off time respects all constraints
+        within_window(takeoff, dep.window.as_ref(), state.soft)
+            .then(|| {
+                vec![DepartureSchedule {
+                    flight_index,
+                    deice: None,
+                    deice_pad: 0,
+                    takeoff,
+                    runway,
+                }]
+            })
+            .ok_or(Constraint::Window)
+    }))
 }
 
 fn expand_deiced_departure(
@@ -193,50 +146,55 @@ fn expand_deiced_departure(
     deice_params: &Deice,
     instance: &Instance,
     state: &BranchBoundState,
-    deice_queue: &HashMap<usize, NaiveDateTime>,
-) -> impl Iterator<Item = DepartureSchedule> {
-    // Find the time when all separation requirements with already scheduled aircraft are satisfied
-    let sep_end = state
-        .current_solution
-        .iter()
-        .rev()
-        .map(|node| {
-            let sep = instance.separations()[(node.sched.flight_index(), flight_index)];
-            node.sched.flight_time() + sep
-        })
-        .max()
-        .unwrap_or(NaiveDateTime::MIN);
-
-    let deice = deice_queue[&flight_index];
-
-    let takeoff = dep
-        .release_time()
-        .max(sep_end)
-        .max(deice + deice_params.duration + dep.taxi_duration + dep.lineup_duration);
-
-    // Ensure that the scheduled take-off time and de-icing time respect all constraints
-    let valid = within_window(takeoff, dep.window.as_ref())
-        && takeoff <= deice + deice_params.duration + deice_params.hot
-        && takeoff
-            <= deice
-                + deice_params.duration
-                + dep.taxi_duration
-                + instance.max_runway_hold_duration
-                + dep.lineup_duration;
+    deice_queue: &HashMap<usize, (NaiveDateTime, usize)>,
+) -> Result<Vec<DepartureSchedule>, Constraint> {
+    let (deice, deice_pad) = deice_queue[&flight_index];
+    let deice_dur = instance.deice_duration(deice_params, deice_pad);
+
+    feasible(instance.allowed_runways(flight_index).map(|runway| {
+        // Find the time when all separation requirements with already scheduled aircraft are satisfied
+        let sep_end = separation_end(instance, state, flight_index, runway);
+
+        let undelayed = dep.release_time().max(sep_end);
+        let takeoff = undelayed.max(deice + deice_dur + dep.taxi_duration + dep.lineup_duration);
+
+        // Ensure that the scheduled take-off time and de-icing time respect all constraints.
+        // If the take-off time is only outside the time window because of the de-icing slot assigned in the de-icing
+        // queue, then the de-icing slot is to blame rather than the time window itself.
+        if !within_window(takeoff, dep.window.as_ref(), state.soft) {
+            return match within_window(undelayed, dep.window.as_ref(), state.soft) {
+                true => Err(Constraint::DeiceSlot),
+                false => Err(Constraint::Window),
+            };
+        }
+        if !state.soft.hot && takeoff > deice + deice_dur + deice_params.hot {
+            return Err(Constraint::Hot);
+        }
+        if !state.soft.runway_hold
+            && takeoff
+                > deice
+                    + deice_dur
+                    + dep.taxi_duration
+                    + instance.max_runway_hold_duration
+                    + dep.lineup_duration
+        {
+            return Err(Constraint::RunwayHold);
+        }
 
-    valid
-        .then_some(DepartureSchedule {
+        Ok(vec![DepartureSchedule {
             flight_index,
             deice: Some(deice),
+            deice_pad,
             takeoff,
-        })
-        .into_iter()
+            runway,
+        }])
+    }))
 }
 
 fn generate_deice_queue<F>(
     instance: &Instance,
     state: &BranchBoundState,
-    deice_queue: &mut HashMap<usize, NaiveDateTime>,
+    deice_queue: &mut HashMap<usize, (NaiveDateTime, usize)>,
     sorter: &mut F,
 ) where
     F: FnMut(&Departure, &Departure) -> Ordering,
@@ -253,48 +211,54 @@ fn generate_deice_queue<F>(
                 .current_solution
                 .iter()
                 .all(|node| node.sched.flight_index() != flight_idx);
-            not_scheduled.then_some((flight_idx, dep, deice))
+            let not_pinned = instance.pin(flight_idx).is_none();
+            (not_scheduled && not_pinned).then_some((flight_idx, dep, deice))
         })
         .collect::<Vec<_>>();
     remaining_departures.sort_unstable_by(|(_, dep, _), (_, other, _)| sorter(dep, other));
 
-    // Find the finish time of the last de-icing operation in the current solution (if any).
-    // This is used as the earliest time any of the remaining departures can start de-icing.
-    let last_deice_end = state
-        .current_solution
-        .iter()
-        .rev()
-        .filter_map(|node| node.sched.as_departure())
-        .filter_map(|sched| match sched.deice {
-            None => None,
-            Some(deice) => {
-                let dep = instance.flights()[sched.flight_index]
-                    .as_departure()
-                    .unwrap();
-                Some(deice + dep.deice.as_ref().unwrap().duration)
-            },
-        })
-        .max();
+    // Find the finish time of the last de-icing operation on each de-icing pad in the current solution (if any).
+    // These are the earliest times any of the remaining departures can start de-icing at each pad.
+    let pad_ends = deice_pad_ends(instance, state);
 
     // Schedule de-icing times for the remaining departures one after the other, respecting their release times
-    // and holdover times
-    let remaining_queue = remaining_departures.into_iter().scan(
-        last_deice_end,
-        |last_deice_end, (flight_idx, dep, deice)| {
-            let mut deice =
-                (dep.release_time() - dep.lineup_duration - dep.taxi_duration - deice.duration)
-                    .max(dep.release_time() - deice.hot - deice.duration);
-            if let Some(last_deice_end) = last_deice_end {
-                deice = deice.max(*last_deice_end);
-            }
+    // and holdover times, and assigning each departure to the de-icing pad where it would finish de-icing earliest
+    let remaining_queue =
+        remaining_departures
+            .into_iter()
+            .scan(pad_ends, |pad_ends, (flight_idx, dep, deice)| {
+                // PANICS: Every instance has at least one de-icing pad, since both its constructors and deserialization
+                //         reject instances without any.
+                let (deice_pad, deice_time, deice_end) = pad_ends
+                    .iter()
+                    .enumerate()
+                    .map(|(pad, pad_end)| {
+                        let deice_dur = instance.deice_duration(deice, pad);
+                        let mut deice_time = (dep.release_time()
+                            - dep.lineup_duration
+                            - dep.taxi_duration
+                            - deice_dur)
+                            .max(dep.release_time() - deice.hot - deice_dur);
+                        if let Some(pad_end) = pad_end {
+                            deice_time = deice_time.max(*pad_end);
+                        }
+                        (pad, deice_time, deice_time + deice_dur)
+                    })
+                    .min_by_key(|&(_, _, deice_end)| deice_end)
+                    .unwrap();
 
-            *last_deice_end = Some(deice);
+                pad_ends[deice_pad] = Some(deice_end);
 
-            Some((flight_idx, deice))
-        },
-    );
+                Some((flight_idx, (deice_time, deice_pad)))
+            });
+
+    // Departures with pinned de-icing slots are always de-iced in those slots
+    let pinned_queue = instance
+        .pinned()
+        .iter()
+        .filter_map(|pin| Some((pin.flight_index(), pin.deice()?)));
 
     // Clear the old de-icing queue and replace it with the new one
     deice_queue.clear();
-    deice_queue.extend(remaining_queue);
+    deice_queue.extend(remaining_queue.chain(pinned_queue));
 }
@@ -0,0 +1,328 @@
+use std::{
+    collections::{hash_map::Entry, BTreeMap, HashMap},
+    time::{Duration, Instant},
+};
+
+use chrono::NaiveDateTime;
+
+use runseq_instance::{
+    schedule::Schedule,
+    solution::{Solution, SolverInfo},
+    solve::{Constraint, Solve, SolveError},
+    Instance,
+};
+
+use crate::{
+    brute_force::expand,
+    complete_orders::separation_identical_complete_orders,
+    cost::{schedule_cost, Cost},
+    objective::Objective,
+    position_shift::PositionShift,
+    search::{deice_pad_ends, separation_end, BranchBoundState, Node, Rejections},
+};
+
+/// An exact dynamic program for solving [`Instance`]s with integrated de-icing, meant as an alternative to
+/// [`BranchBound`](crate::BranchBound) for instances whose aircraft fall into few complete-order sets.
+///
+/// The aircraft in each set of separation-identical aircraft with a complete order are always sequenced in that order,
+/// so a partial sequence is described by the number of aircraft it contains from each set.
+/// Partial sequences are extended one aircraft at a time like in [`BruteForce`](crate::BruteForce), so every
+/// departure that needs to be de-iced is tried on every de-icing pad and at every minute that it can be de-iced at.
+///
+/// Two partial sequences dominate each other if they contain the same aircraft, the next aircraft in every set is
+/// separated from all sequenced aircraft by the same time on every runway, and every de-icing pad becomes free at the
+/// same time.
+/// Only the cheapest of them is extended further, since the remaining aircraft can be scheduled in exactly the same
+/// ways after either of them.
+/// This generalises the state of the last aircraft and the last de-icing time to separations that do not satisfy the
+/// triangle inequality and to several de-icing pads.
+///
+/// Aircraft with [pinned](runseq_instance::pin::Pin) schedules are sequenced before all other aircraft, and are part
+/// of every partial sequence.
+/// Aircraft are never moved further from their first-come-first-served positions than allowed by
+/// [`Instance::max_position_shift`].
+///
+/// No other rules are used to discard partial sequences, which makes the solver a reference for checking the optimality
+/// of other solvers, but the number of partial sequences grows exponentially with the number of complete-order sets.
+///
+/// Like every other solver, it only considers sequences in which every aircraft lands or takes off as early as
+/// possible after the aircraft before it, and in which departures are de-iced in the order they take off in.
+/// The sequences it produces are marked as optimal unless the objective can reward landing or taking off later than
+/// possible, as [`Objective::ctot_early`] and [`Shift::Time`](crate::Shift::Time) stability do.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct DynamicProgram {
+    /// The objective function to minimise.
+    pub objective: Objective,
+}
+
+// Everything about a partial sequence that determines how the remaining aircraft can be scheduled
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+struct State {
+    // The number of aircraft sequenced from each complete-order set
+    positions: Vec<usize>,
+    // The time at which the next aircraft in each complete-order set is separated from all sequenced aircraft, for
+    // each runway
+    separation_ends: Vec<NaiveDateTime>,
+    // The time at which each de-icing pad becomes free, if it has been used
+    deice_pad_ends: Vec<Option<NaiveDateTime>>,
+}
+
+#[derive(Debug, Clone)]
+struct Label {
+    state: State,
+    cost: Cost,
+    // The last scheduled aircraft and its complete-order set, along with the label it extends
+    last: Option<(Schedule, usize)>,
+    parent: usize,
+}
+
+// A label in the current layer, along with the partial sequence ending in it
+#[derive(Debug, Clone)]
+struct Partial {
+    label_idx: usize,
+    sequence: Vec<Node>,
+}
+
+impl DynamicProgram {
+    fn solver_info(&self) -> SolverInfo {
+        let mut settings = BTreeMap::new();
+        if self.objective != Objective::default() {
+            settings.insert("objective".to_owned(), format!("{:?}", self.objective));
+        }
+
+        SolverInfo {
+            name: "dynamic-program".to_owned(),
+            settings,
+        }
+    }
+}
+
+impl Solve for DynamicProgram {
+    fn solve(&self, instance: &Instance) -> Result<Solution, SolveError> {
+        let start = Instant::now();
+
+        let flight_count = instance.flights().len();
+        let runway_count = instance.runways().len();
+        let complete_order_sets = separation_identical_complete_orders(instance, &self.objective);
+        let position_shift = PositionShift::new(instance);
+
+        // NOTE: Every partial sequence starts with the aircraft whose schedules are pinned, so the first label
+        //       describes the state after them rather than an empty sequence.
+        let mut state = BranchBoundState::without_complete_orders(instance, &self.objective);
+        let mut separation_ends =
+            vec![NaiveDateTime::MIN; complete_order_sets.len() * runway_count];
+        for (set_idx, complete_order_set) in complete_order_sets.iter().enumerate() {
+            let Some(&first_idx) = complete_order_set.first() else {
+                continue;
+            };
+            for runway in 0..runway_count {
+                separation_ends[set_idx * runway_count + runway] =
+                    separation_end(instance, &state, first_idx, runway);
+            }
+        }
+
+        let mut labels = vec![Label {
+            state: State {
+                positions: vec![0; complete_order_sets.len()],
+                separation_ends,
+                deice_pad_ends: deice_pad_ends(instance, &state),
+            },
+            cost: Cost::default(),
+            last: None,
+            parent: 0,
+        }];
+        let mut layer = vec![Partial {
+            label_idx: 0,
+            sequence: std::mem::take(&mut state.current_solution),
+        }];
+        let mut rejections = Rejections::default();
+
+        // NOTE: The partial sequence of every label in the current layer is kept around for expanding it, since it is
+        //       needed to separate the next aircraft from all sequenced aircraft.
+        //       The partial sequence being expanded is moved into the state while it is expanded.
+
+        for depth in layer[0].sequence.len()..flight_count {
+            let mut next_layer = Vec::new();
+            let mut states = HashMap::new();
+
+            for partial in &mut layer {
+                let label_idx = partial.label_idx;
+                state.current_solution = std::mem::take(&mut partial.sequence);
+
+                for (complete_order_idx, complete_order_set) in
+                    complete_order_sets.iter().enumerate()
+                {
+                    let label = &labels[label_idx];
+                    let Some(&flight_idx) =
+                        complete_order_set.get(label.state.positions[complete_order_idx])
+                    else {
+                        continue;
+                    };
+                    let flight = &instance.flights()[flight_idx];
+
+                    // NOTE: Whether an aircraft can be sequenced next without moving any aircraft too far from its
+                    //       first-come-first-served position only depends on which aircraft are sequenced already,
+                    //       which is the same for every partial sequence ending in the same state.
+                    if position_shift
+                        .as_ref()
+                        .is_some_and(|shift| !shift.allows(flight_idx, &state.current_solution))
+                    {
+                        rejections.record(depth, flight_idx, Constraint::PositionShift);
+                        continue;
+                    }
+
+                    let scheds = match expand(flight, flight_idx, instance, &state) {
+                        Ok(scheds) => scheds,
+                        Err(constraint) => {
+                            rejections.record(depth, flight_idx, constraint);
+                            continue;
+                        },
+                    };
+
+                    for sched in scheds {
+                        let label = &labels[label_idx];
+                        let cost = label.cost
+                            + schedule_cost(
+                                &sched,
+                                depth,
+                                instance,
+                                &self.objective,
+                                &state.reference_positions,
+                            );
+                        let next_state = extend(
+                            &label.state,
+                            &sched,
+                            complete_order_idx,
+                            &complete_order_sets,
+                            instance,
+                        );
+                        let next_label = Label {
+                            state: next_state.clone(),
+                            cost,
+                            last: Some((sched, complete_order_idx)),
+                            parent: label_idx,
+                        };
+
+                        // Keep only the cheapest partial sequence ending in each state, preferring the one found first
+                        match states.entry(next_state) {
+                            Entry::Vacant(entry) => {
+                                entry.insert(labels.len());
+                                next_layer.push(labels.len());
+                                labels.push(next_label);
+                            },
+                            Entry::Occupied(entry) => {
+                                let existing = &mut labels[*entry.get()];
+                                if cost < existing.cost {
+                                    *existing = next_label;
+                                }
+                            },
+                        }
+                    }
+                }
+
+                partial.sequence = std::mem::take(&mut state.current_solution);
+            }
+
+            if next_layer.is_empty() {
+                return Err(SolveError::Infeasible {
+                    window: depth..flight_count,
+                    flights: rejections.into_infeasible_flights(),
+                });
+            }
+
+            // Extend the partial sequence of every parent by the last aircraft of each label that extends it
+            let parents = layer
+                .into_iter()
+                .map(|partial| (partial.label_idx, partial.sequence))
+                .collect::<HashMap<_, _>>();
+            layer = next_layer
+                .into_iter()
+                .map(|label_idx| {
+                    let label = &labels[label_idx];
+                    let parent = &labels[label.parent];
+                    // PANICS: Every label in the next layer extends a label in the current layer with an aircraft.
+                    let (sched, complete_order_idx) = label.last.clone().unwrap();
+                    let mut sequence = parents[&label.parent].clone();
+                    sequence.push(Node {
+                        sched,
+                        depth,
+                        complete_order_idx,
+                        cost: label.cost.saturating_sub(parent.cost),
+                    });
+                    Partial {
+                        label_idx,
+                        sequence,
+                    }
+                })
+                .collect();
+        }
+
+        // PANICS: The last layer always contains at least one label, since the search returns early otherwise.
+        let best = layer
+            .into_iter()
+            .min_by_key(|partial| labels[partial.label_idx].cost)
+            .unwrap();
+        let schedules = best.sequence.into_iter().map(|node| node.sched).collect();
+
+        let mut solution = Solution::new(schedules, instance);
+        solution.solver = Some(self.solver_info());
+        solution.wall_time = Some(start.elapsed());
+        solution.nodes = Some(labels.len() as u64);
+        solution.optimal = !self.objective.rewards_later_times();
+
+        Ok(solution)
+    }
+}
+
+fn extend(
+    state: &State,
+    sched: &Schedule,
+    complete_order_idx: usize,
+    complete_order_sets: &[Vec<usize>],
+    instance: &Instance,
+) -> State {
+    let runway_count = instance.runways().len();
+
+    let mut positions = state.positions.clone();
+    positions[complete_order_idx] += 1;
+
+    // NOTE: The aircraft in a complete-order set are separation-identical, so every remaining aircraft in a set is
+    //       separated from the scheduled aircraft in the same way as the next one.
+    //       Sets without remaining aircraft are reset so that they never tell two states apart.
+    let mut separation_ends = state.separation_ends.clone();
+    for (set_idx, complete_order_set) in complete_order_sets.iter().enumerate() {
+        let ends = &mut separation_ends[set_idx * runway_count..(set_idx + 1) * runway_count];
+        let Some(&next_idx) = complete_order_set.get(positions[set_idx]) else {
+            ends.fill(NaiveDateTime::MIN);
+            continue;
+        };
+        for (runway, end) in ends.iter_mut().enumerate() {
+            let sep = instance
+                .separation(sched.flight_index(), sched.runway(), next_idx, runway)
+                .unwrap_or(Duration::ZERO);
+            *end = (*end).max(sched.flight_time() + sep);
+        }
+    }
+
+    let mut deice_pad_ends = state.deice_pad_ends.clone();
+    let deice_end = sched.as_departure().and_then(|sched| {
+        let deice = sched.deice?;
+        let params = instance.flights()[sched.flight_index]
+            .as_departure()?
+            .deice
+            .as_ref()?;
+        Some((
+            sched.deice_pad,
+            deice + instance.deice_duration(params, sched.deice_pad),
+        ))
+    });
+    if let Some((pad, deice_end)) = deice_end {
+        deice_pad_ends[pad] = deice_pad_ends[pad].max(Some(deice_end));
+    }
+
+    State {
+        positions,
+        separation_ends,
+        deice_pad_ends,
+    }
+}
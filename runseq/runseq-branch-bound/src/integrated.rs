@@ -1,82 +1,65 @@
-use std::num::NonZeroUsize;
-
 use chrono::NaiveDateTime;
-use either::{Left, Right};
 
 use runseq_instance::{
     flight::{Arrival, Deice, Departure, Flight},
+    pin::Pin,
     schedule::{ArrivalSchedule, DepartureSchedule, Schedule},
+    solve::{Constraint, SolveError},
     Instance,
 };
 
-use crate::search::{branch_bound, iter_minutes, within_window, BranchBoundState};
+use crate::{
+    rolling::rolling_horizon,
+    search::{
+        branch_bound,
+        deice_pad_ends,
+        feasible,
+        iter_minutes,
+        separation_end,
+        within_window,
+        BranchBoundState,
+        Deicing,
+        SearchOptions,
+    },
+    RollingHorizon,
+};
 
 pub fn branch_bound_rolling(
     instance: &Instance,
-    horizon: Option<NonZeroUsize>,
-) -> Option<Vec<Schedule>> {
-    let flight_count = instance.flights().len();
-
-    let mut state = BranchBoundState::new(instance);
-
-    let end = horizon
-        .map(usize::from)
-        .unwrap_or(flight_count)
-        .min(flight_count);
-
-    let mut nodes = Vec::with_capacity(flight_count);
-
-    // Perform branch-and-bound for the first window
-    branch_bound(instance, &mut state, &mut nodes, &mut expand, 0..end);
-
-    // Perform branch-and-bound for the remaining windows
-    let windows = (1..)
-        .zip(end + 1..=flight_count)
-        .map(|(start, end)| start..end);
-    for window in windows {
-        // Ignore all scheduled aircraft except for the first one
-        let fixed = state.best_solution.drain(..).next()?;
-        let fixed_idx = fixed.sched.flight_index();
-
-        // Save the first aircraft to the current solution
-        state.current_solution.push(fixed);
-
-        // Remove that aircraft from the sets of complete orders
-        state.next_in_complete_order_sets.fill(0);
-        for set in &mut state.complete_order_sets {
-            set.retain(|&flight_idx| flight_idx != fixed_idx);
-        }
+    horizon: Option<&RollingHorizon>,
+    options: &SearchOptions<'_>,
+) -> Result<Vec<Schedule>, SolveError> {
+    let mut state = BranchBoundState::new(instance, options.objective);
 
-        // Perform branch-and-bound for the current window
-        branch_bound(instance, &mut state, &mut nodes, &mut expand, window);
-    }
-
-    // Ensure that a feasible last solution is produced
-    let last_best_solution = (!state.best_solution.is_empty()).then_some(state.best_solution)?;
-
-    let solution = state
-        .current_solution
-        .into_iter()
-        .chain(last_best_solution)
-        .map(|node| node.sched)
-        .collect();
-    Some(solution)
+    rolling_horizon(instance, horizon, &mut state, |state, window| {
+        branch_bound(
+            instance,
+            state,
+            &expand,
+            Deicing::Integrated,
+            window,
+            options,
+        )
+    })
 }
 
-fn expand(
+pub fn expand(
     flight: &Flight,
     flight_idx: usize,
     instance: &Instance,
     state: &BranchBoundState,
-) -> impl Iterator<Item = Schedule> {
-    match flight {
-        Flight::Arr(arr) => {
-            Left(expand_arrival(arr, flight_idx, instance, state).map(Schedule::Arr))
-        },
-        Flight::Dep(dep) => {
-            Right(expand_departure(dep, flight_idx, instance, state).map(Schedule::Dep))
-        },
-    }
+) -> Result<Vec<Schedule>, Constraint> {
+    let scheds = match flight {
+        Flight::Arr(arr) => expand_arrival(arr, flight_idx, instance, state)?
+            .into_iter()
+            .map(Schedule::Arr)
+            .collect(),
+        Flight::Dep(dep) => expand_departure(dep, flight_idx, instance, state)?
+            .into_iter()
+            .map(Schedule::Dep)
+            .collect(),
+    };
+    Ok(scheds)
 }
 
 fn expand_arrival(
@@ -84,28 +67,24 @@ fn expand_arrival(
     flight_index: usize,
     instance: &Instance,
     state: &BranchBoundState,
-) -> impl Iterator<Item = ArrivalSchedule> {
-    // Find the time when all separation requirements with already scheduled aircraft are satisfied
-    let sep_end = state
-        .current_solution
-        .iter()
-        .rev()
-        .map(|node| {
-            let sep = instance.separations()[(node.sched.flight_index(), flight_index)];
-            node.sched.flight_time() + sep
-        })
-        .max()
-        .unwrap_or(NaiveDateTime::MIN);
-
-    let landing = arr.release_time().max(sep_end);
-
-    // Ensure that the scheduled landing time respects all constraints
-    within_window(landing, arr.window.as_ref())
-        .then_some(ArrivalSchedule {
-            flight_index,
-            landing,
-        })
-        .into_iter()
+) -> Result<Vec<ArrivalSchedule>, Constraint> {
+    feasible(instance.allowed_runways(flight_index).map(|runway| {
+        // Find the time when all separation requirements with already scheduled aircraft are satisfied
+        let sep_end = separation_end(instance, state, flight_index, runway);
+
+        let landing = arr.release_time().max(sep_end);
+
+        // Ensure that the scheduled landing time respects all constraints
+        within_window(landing, arr.window.as_ref(), state.soft)
+            .then(|| {
+                vec![ArrivalSchedule {
+                    flight_index,
+                    landing,
+                    runway,
+                }]
+            })
+            .ok_or(Constraint::Window)
+    }))
 }
 
 fn expand_departure(
@@ -113,16 +92,13 @@ fn expand_departure(
     flight_index: usize,
     instance: &Instance,
     state: &BranchBoundState,
-) -> impl Iterator<Item = DepartureSchedule> {
-    match &dep.deice {
-        None => {
-            let scheds = expand_direct_departure(dep, flight_index, instance, state);
-            Left(scheds)
-        },
-        Some(deice) => {
-            let scheds = expand_deiced_departure(dep, flight_index, deice, instance, state);
-            Right(scheds)
+) -> Result<Vec<DepartureSchedule>, Constraint> {
+    match (&dep.deice, instance.pin(flight_index).and_then(Pin::deice)) {
+        (None, _) => expand_direct_departure(dep, flight_index, instance, state),
+        (Some(deice), Some(slot)) => {
+            expand_pinned_departure(dep, flight_index, deice, slot, instance, state)
         },
+        (Some(deice), None) => expand_deiced_departure(dep, flight_index, deice, instance, state),
     }
 }
 
@@ -131,29 +107,26 @@ fn expand_direct_departure(
     flight_index: usize,
     instance: &Instance,
     state: &BranchBoundState,
-) -> impl Iterator<Item = DepartureSchedule> {
-    // Find the time when all separation requirements with already scheduled aircraft are satisfied
-    let sep_end = state
-        .current_solution
-        .iter()
-        .rev()
-        .map(|node| {
-            let sep = instance.separations()[(node.sched.flight_index(), flight_index)];
-            node.sched.flight_time() + sep
-        })
-        .max()
-        .unwrap_or(NaiveDateTime::MIN);
-
-    let takeoff = dep.release_time().max(sep_end);
-
-    // Ensure that the scheduled take-off time respects all constraints
-    within_window(takeoff, dep.window.as_ref())
-        .then_some(DepartureSchedule {
-            flight_index,
-            deice: None,
-            takeoff,
-        })
-        .into_iter()
+) -> Result<Vec<DepartureSchedule>, Constraint> {
+    feasible(instance.allowed_runways(flight_index).map(|runway| {
+        // Find the time when all separation requirements with already scheduled aircraft are satisfied
+        let sep_end = separation_end(instance, state, flight_index, runway);
+
+        let takeoff = dep.release_time().max(sep_end);
+
+        // Ensure that the scheduled take-off time respects all constraints
+        within_window(takeoff, dep.window.as_ref(), state.soft)
+            .then(|| {
+                vec![DepartureSchedule {
+                    flight_index,
+                    deice: None,
+                    deice_pad: 0,
+                    takeoff,
+                    runway,
+                }]
+            })
+            .ok_or(Constraint::Window)
+    }))
 }
 
 fn expand_deiced_departure(
@@ -162,80 +135,142 @@ fn expand_deiced_departure(
     deice: &Deice,
     instance: &Instance,
     state: &BranchBoundState,
-) -> impl Iterator<Item = DepartureSchedule> {
-    // Find the time when all separation requirements with already scheduled aircraft are satisfied
-    let sep_end = state
-        .current_solution
-        .iter()
-        .rev()
-        .map(|node| {
-            let sep = instance.separations()[(node.sched.flight_index(), flight_index)];
-            node.sched.flight_time() + sep
-        })
-        .max()
-        .unwrap_or(NaiveDateTime::MIN);
-
-    // Find the time when the last de-icing aircraft finishes de-icing
-    let deice_end = state
-        .current_solution
-        .iter()
-        .rev()
-        .filter_map(|node| {
-            let sched = node.sched.as_departure()?;
-            let deice_time = sched.deice?;
-            let deice_dur = instance.flights()[sched.flight_index]
-                .as_departure()?
-                .deice
-                .as_ref()?
-                .duration;
-            Some(deice_time + deice_dur)
-        })
-        .max();
-
-    let (earliest_deice, latest_deice, takeoff) = match deice_end {
-        None => {
-            let takeoff = dep.release_time().max(sep_end);
-
-            // If no aircraft was previously de-icing, then the current departure can be de-iced as soon as possible
-            let earliest_deice = (takeoff
-                - instance.max_runway_hold_duration
-                - dep.lineup_duration
-                - dep.taxi_duration
-                - deice.duration)
-                .max(takeoff - deice.hot - deice.duration);
-            let latest_deice = takeoff - dep.lineup_duration - dep.taxi_duration - deice.duration;
-
-            (earliest_deice, latest_deice, takeoff)
-        },
-        Some(deice_end) => {
-            let takeoff = dep
-                .release_time()
-                .max(sep_end)
-                .max(deice_end + deice.duration + dep.taxi_duration + dep.lineup_duration);
-
-            // If there was an aircraft that was previously de-icing, then the current departure can only de-ice after
-            // that one has finished
-            let earliest_deice = (takeoff
-                - instance.max_runway_hold_duration
-                - dep.lineup_duration
-                - dep.taxi_duration
-                - deice.duration)
-                .max(takeoff - deice.hot - deice.duration)
-                .max(deice_end);
-            let latest_deice = takeoff - dep.lineup_duration - dep.taxi_duration - deice.duration;
-
-            (earliest_deice, latest_deice, takeoff)
-        },
-    };
-
-    // Ensure that the scheduled take-off time and de-icing time respect all constraints
-    within_window(takeoff, dep.window.as_ref())
-        .then_some(iter_minutes(earliest_deice, latest_deice))
+) -> Result<Vec<DepartureSchedule>, Constraint> {
+    // Find the de-icing pad that becomes free the earliest, preferring faster pads if several are free at the same
+    // time
+    // NOTE: The other pads are never tried, which keeps the search tree as small as with a single pad, but can miss
+    //       better sequences when there are several pads.
+    // PANICS: Every instance has at least one de-icing pad, since both its constructors and deserialization reject
+    //         instances without any.
+    let pad = deice_pad_ends(instance, state)
         .into_iter()
-        .flatten()
-        .map(move |deice| DepartureSchedule {
+        .enumerate()
+        .min_by_key(|&(pad, pad_end)| (pad_end, instance.deice_duration(deice, pad)))
+        .unwrap();
+
+    feasible(expand_deiced_departure_at(
+        dep,
+        flight_index,
+        deice,
+        pad,
+        instance,
+        state,
+    ))
+}
+
+// Schedules a departure that needs to be de-iced at the given de-icing pad on each of its allowed runways, given when
+// the de-icing pad becomes free if any departure has already been de-iced at it
+pub fn expand_deiced_departure_at<'a>(
+    dep: &'a Departure,
+    flight_index: usize,
+    deice: &'a Deice,
+    (deice_pad, deice_end): (usize, Option<NaiveDateTime>),
+    instance: &'a Instance,
+    state: &'a BranchBoundState,
+) -> impl Iterator<Item = Result<Vec<DepartureSchedule>, Constraint>> + 'a {
+    let deice_dur = instance.deice_duration(deice, deice_pad);
+
+    instance.allowed_runways(flight_index).map(move |runway| {
+        // Find the time when all separation requirements with already scheduled aircraft are satisfied
+        let sep_end = separation_end(instance, state, flight_index, runway);
+        let undelayed = dep.release_time().max(sep_end);
+
+        // If another departure was previously de-icing at the pad, then the current departure can only de-ice after
+        // that one has finished
+        let mut takeoff = undelayed;
+        if let Some(deice_end) = deice_end {
+            takeoff = takeoff.max(deice_end + deice_dur + dep.taxi_duration + dep.lineup_duration);
+        }
+
+        let mut earliest_deice = (takeoff
+            - instance.max_runway_hold_duration
+            - dep.lineup_duration
+            - dep.taxi_duration
+            - deice_dur)
+            .max(takeoff - deice.hot - deice_dur);
+        if let Some(deice_end) = deice_end {
+            earliest_deice = earliest_deice.max(deice_end);
+        }
+        let latest_deice = takeoff - dep.lineup_duration - dep.taxi_duration - deice_dur;
+
+        // Ensure that the scheduled take-off time and de-icing time respect all constraints.
+        // If the take-off time is only outside the time window because of when the de-icing pad becomes free, then
+        // the de-icing slot is to blame rather than the time window itself.
+        if !within_window(takeoff, dep.window.as_ref(), state.soft) {
+            return match within_window(undelayed, dep.window.as_ref(), state.soft) {
+                true => Err(Constraint::DeiceSlot),
+                false => Err(Constraint::Window),
+            };
+        }
+
+        // NOTE: The de-icing times can only be empty if the HOT is too short to taxi out and line up in time.
+        //       If HOTs are soft constraints, the departure is then de-iced as late as possible to exceed its HOT by
+        //       as little as possible.
+        if earliest_deice > latest_deice {
+            if !state.soft.hot {
+                return Err(Constraint::Hot);
+            }
+            earliest_deice = latest_deice;
+        }
+
+        let scheds = iter_minutes(earliest_deice, latest_deice)
+            .map(|deice| DepartureSchedule {
+                flight_index,
+                deice: Some(deice),
+                deice_pad,
+                takeoff,
+                runway,
+            })
+            .collect();
+        Ok(scheds)
+    })
+}
+
+fn expand_pinned_departure(
+    dep: &Departure,
+    flight_index: usize,
+    deice: &Deice,
+    (deice_time, deice_pad): (NaiveDateTime, usize),
+    instance: &Instance,
+    state: &BranchBoundState,
+) -> Result<Vec<DepartureSchedule>, Constraint> {
+    let deice_end = deice_time + instance.deice_duration(deice, deice_pad);
+
+    feasible(instance.allowed_runways(flight_index).map(|runway| {
+        // Find the time when all separation requirements with already scheduled aircraft are satisfied
+        let sep_end = separation_end(instance, state, flight_index, runway);
+
+        // The departure can only take off once it has taxied out and lined up after its pinned de-icing slot
+        let undelayed = dep.release_time().max(sep_end);
+        let takeoff = undelayed.max(deice_end + dep.taxi_duration + dep.lineup_duration);
+
+        // Ensure that the scheduled take-off time respects all constraints, although the de-icing time itself can
+        // no longer change
+        if !within_window(takeoff, dep.window.as_ref(), state.soft) {
+            return match within_window(undelayed, dep.window.as_ref(), state.soft) {
+                true => Err(Constraint::DeiceSlot),
+                false => Err(Constraint::Window),
+            };
+        }
+        if !state.soft.hot && takeoff > deice_end + deice.hot {
+            return Err(Constraint::Hot);
+        }
+        if !state.soft.runway_hold
+            && takeoff
+                > deice_end
+                    + dep.taxi_duration
+                    + dep.lineup_duration
+                    + instance.max_runway_hold_duration
+        {
+            return Err(Constraint::RunwayHold);
+        }
+
+        Ok(vec![DepartureSchedule {
             flight_index,
-            deice: Some(deice),
+            deice: Some(deice_time),
+            deice_pad,
             takeoff,
-        })
+            runway,
+        }])
+    }))
 }
@@ -3,28 +3,145 @@
 #![deny(rust_2018_idioms)]
 #![warn(missing_docs)]
 
-use std::num::NonZeroUsize;
+use std::{
+    collections::BTreeMap,
+    num::NonZeroUsize,
+    time::{Duration, Instant},
+};
 
-use runseq_instance::{schedule::Schedule, solve::Solve, Instance};
+use runseq_instance::{
+    event::Update,
+    schedule::Schedule,
+    solution::{Solution, SolverInfo},
+    solve::{CancelToken, Solve, SolveError},
+    verify::verify,
+    Instance,
+};
+
+mod brute_force;
+pub use brute_force::{compare_with_brute_force, BruteForce, Comparison};
 
 mod complete_orders;
 
 mod cost;
-pub use cost::solution_cost;
+pub use cost::{cost_breakdown, solution_cost, Cost, CostBreakdown, ScheduleCost};
+
+mod decode;
+pub use decode::decode;
+
+mod dp;
+pub use dp::DynamicProgram;
+
+mod objective;
+pub use objective::{Objective, Penalty, Piecewise, Segment, Shift, Term};
+
+mod position_shift;
+
+mod rolling;
 
 mod search;
+use search::{Limits, SearchOptions};
 
 mod decomposed;
 
 mod integrated;
 
 /// A branch-and-bound solver for solving [`Instance`]s.
+///
+/// If the time limit or node limit is reached or the solver is cancelled, it stops and returns the best sequence found
+/// so far, which is never marked as optimal.
+/// The windows of the rolling horizon that have already been fixed are kept, and every window without a sequence by
+/// then is sequenced greedily instead, by always sequencing the aircraft that the search would have tried first.
+/// Windows covered by a [warm start sequence](WarmStart::Sequence) fall back on that sequence instead.
+/// If the greedy sequence of a window turns out to be infeasible, it returns [`SolveError::LimitReached`].
 #[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
 pub struct BranchBound {
-    /// The size of the rolling horizon to use when solving an instance, if any.
-    pub horizon: Option<NonZeroUsize>,
+    /// The rolling horizon to use when solving an instance, if any.
+    pub horizon: Option<RollingHorizon>,
     /// The de-icing strategy to use when solving an instance.
     pub deice_strategy: DeiceStrategy,
+    /// The maximum wall-clock time to spend solving an instance, if any.
+    pub time_limit: Option<Duration>,
+    /// The maximum number of nodes to explore when solving an instance, if any.
+    ///
+    /// Nodes are counted across all windows of the rolling horizon.
+    pub node_limit: Option<u64>,
+    /// A token that can be used to stop the solver from another thread, if any.
+    pub cancel_token: Option<CancelToken>,
+    /// The objective function to minimise.
+    pub objective: Objective,
+    /// The number of threads to search with, if more than one.
+    ///
+    /// The search tree is split into subtrees which are searched in parallel, with the best sequence found so far
+    /// shared between threads for pruning.
+    /// Subtrees are checked against the best sequence that the sequential search would have known when reaching them,
+    /// and searched again if needed, so the parallel search always produces the same sequence as the sequential search
+    /// regardless of the number of threads, even with a rolling horizon.
+    /// This does not hold if the search is stopped early because a limit was reached.
+    ///
+    /// Only [`SearchStrategy::DepthFirst`] and [`SearchStrategy::LimitedDiscrepancy`] make use of multiple threads.
+    pub threads: Option<NonZeroUsize>,
+    /// The order in which to explore the search tree.
+    pub strategy: SearchStrategy,
+    /// The lower bound used to estimate the cost of the remaining aircraft when pruning the search tree.
+    pub lower_bound: LowerBound,
+    /// A known sequence or upper bound to start the search from, if any.
+    pub warm_start: Option<WarmStart>,
+}
+
+/// A rolling horizon, which splits the sequence into windows that are searched one after the other.
+///
+/// Every window sequences the next few aircraft that have not been fixed yet.
+/// Once a window has been searched, the first few aircraft in it are fixed in place, and the next window continues
+/// after them.
+/// This allows large instances to be solved much more quickly, but the resulting sequence is not necessarily the best
+/// one.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct RollingHorizon {
+    /// The number of aircraft that have not been fixed yet to sequence in every window.
+    pub window: Window,
+    /// The number of aircraft to fix at the start of every window before moving on to the next window.
+    ///
+    /// Fixing more aircraft at once means that fewer windows need to be searched, but that each aircraft is
+    /// sequenced with less knowledge of the aircraft after it.
+    pub commit: NonZeroUsize,
+    /// The number of most recently fixed aircraft to sequence again along with every window.
+    ///
+    /// This allows a window to change the sequence of the aircraft fixed by the previous window, which were sequenced
+    /// with less knowledge of the aircraft after them.
+    pub overlap: usize,
+}
+
+impl RollingHorizon {
+    /// Creates a rolling horizon with windows of a fixed number of aircraft, which fixes one aircraft per window.
+    pub const fn flights(size: NonZeroUsize) -> Self {
+        Self {
+            window: Window::Flights(size),
+            commit: NonZeroUsize::MIN,
+            overlap: 0,
+        }
+    }
+
+    /// Creates a rolling horizon with windows covering a fixed duration, which fixes one aircraft per window.
+    pub const fn duration(duration: Duration) -> Self {
+        Self {
+            window: Window::Duration(duration),
+            commit: NonZeroUsize::MIN,
+            overlap: 0,
+        }
+    }
+}
+
+/// The size of a [`RollingHorizon`] window.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Window {
+    /// A fixed number of aircraft.
+    Flights(NonZeroUsize),
+    /// All aircraft with release times before the given duration has passed since the earliest release time of any
+    /// aircraft that has not been fixed yet.
+    ///
+    /// Every window contains at least one aircraft, and windows contain more aircraft during busier periods.
+    Duration(Duration),
 }
 
 /// Different de-icing strategies used for solving an [`Instance`].
@@ -45,29 +162,320 @@ pub enum DeiceStrategy {
     ///
     /// This strategy assigns landings, take-offs, and de-icing times to all aircraft together, rather than
     /// solving one problem first.
+    /// Every departure is de-iced on the de-icing pad that becomes free the earliest, and the search never branches over
+    /// the other pads, so it can miss sequences in which a departure is better off waiting for a different pad.
+    /// Sequences are therefore only marked as optimal if the instance has a single de-icing pad.
     #[default]
     Integrated,
 }
 
-impl Solve for BranchBound {
-    fn solve(&self, instance: &Instance) -> Option<Vec<Schedule>> {
-        match self.deice_strategy {
-            DeiceStrategy::ByTobt => {
-                decomposed::branch_bound_rolling(instance, self.horizon, |dep, other| {
-                    dep.tobt.cmp(&other.tobt)
-                })
+/// Different orders in which the search tree can be explored when solving an [`Instance`].
+///
+/// Candidate aircraft are always sorted by their release times before being explored.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub enum SearchStrategy {
+    /// Depth-first search.
+    ///
+    /// This strategy explores the first candidate at every level before backtracking, and finds the best sequence.
+    #[default]
+    DepthFirst,
+    /// Best-first search.
+    ///
+    /// This strategy always explores the candidate with the lowest cost plus estimated remaining cost next, and finds
+    /// the best sequence.
+    /// It tends to explore fewer nodes than depth-first search, but keeps more of them in memory.
+    BestFirst,
+    /// Limited discrepancy search.
+    ///
+    /// This strategy performs repeated depth-first searches that may deviate from the first candidate aircraft at a
+    /// limited number of levels, starting with none and increasing by one with every search.
+    /// Every schedule of the first candidate aircraft, such as on another runway or with another de-icing time, is
+    /// explored without counting as a deviation.
+    /// It only finds the best sequence if the number of discrepancies is large enough.
+    LimitedDiscrepancy {
+        /// The maximum number of levels at which a candidate other than the first may be explored.
+        discrepancies: usize,
+    },
+    /// Beam search.
+    ///
+    /// This strategy explores the search tree one level at a time, and only keeps the candidates with the lowest cost
+    /// plus estimated remaining cost at every level.
+    /// It quickly finds good sequences for large instances, but does not necessarily find the best one.
+    Beam {
+        /// The number of candidates to keep at every level.
+        width: NonZeroUsize,
+    },
+}
+
+/// Different lower bounds on the cost of the aircraft that remain to be sequenced, used to prune the search tree.
+///
+/// A tighter lower bound prunes more of the search tree, but takes longer to calculate for every node.
+/// Both lower bounds include every aircraft that remains to be sequenced, even those beyond the current rolling horizon
+/// window.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub enum LowerBound {
+    /// A simple lower bound.
+    ///
+    /// This bound schedules every remaining aircraft as soon as possible after the last sequenced aircraft, ignoring
+    /// separations and de-icing.
+    #[default]
+    Simple,
+    /// A tight lower bound.
+    ///
+    /// This bound additionally accounts for the minimum separations between aircraft in the same complete-order set,
+    /// the separations with already sequenced aircraft, and the earliest time each departure can finish de-icing.
+    Tight,
+}
+
+/// A starting point for the search, used to prune the search tree from the very first node.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum WarmStart {
+    /// A known feasible sequence, such as a previous plan or a sequence produced by another solver.
+    ///
+    /// The sequence is checked for feasibility before solving, although it may violate constraints that are
+    /// [penalised](Objective::penalises) by the objective, such as departures taking off after their CTOT slot.
+    /// Within every rolling horizon window, the aircraft are sequenced in the order they appear in the sequence
+    /// (as far as the search tree allows) and re-timed, which gives the initial best known sequence of the window.
+    Sequence(Vec<Schedule>),
+    /// An upper bound on the objective value of the best sequence, as returned by [`Cost::as_u64`].
+    ///
+    /// Only sequences with an objective value up to the upper bound are searched for.
+    /// If a rolling horizon window has no such sequence, it is searched again without the upper bound.
+    UpperBound(u64),
+}
+
+impl BranchBound {
+    /// Solves an instance again after it has been changed by [`Event`](runseq_instance::event::Event)s, starting from
+    /// the sequence produced for the instance before the change.
+    ///
+    /// The previous sequence is remapped to the aircraft indices of the updated instance and becomes its
+    /// [reference sequence](Instance::with_reference), replacing any reference sequence it already had.
+    /// This only keeps the new sequence close to the previous one if [`Objective::stability`] is penalised.
+    /// It is also used as a [`WarmStart::Sequence`], replacing any warm start the solver is configured with.
+    /// If the remapped sequence is no longer feasible or aircraft were added, it is first re-timed in the same order
+    /// using [`decode`], with added aircraft inserted by their release times.
+    /// If even that is infeasible, the instance is solved without a warm start.
+    ///
+    /// Returns the new sequence along with the updated instance including its reference sequence, which the sequence
+    /// should be evaluated against.
+    pub fn resolve(
+        &self,
+        update: &Update,
+        previous: &[Schedule],
+    ) -> Result<(Solution, Instance), SolveError> {
+        let reference = update.remap.schedules(previous);
+
+        // PANICS: Remapped sequences never contain aircraft that do not exist or that are scheduled more than once.
+        let instance = update
+            .instance
+            .clone()
+            .with_reference(reference.clone())
+            .unwrap();
+
+        let mut added = update.remap.added().collect::<Vec<_>>();
+        let feasible = added.is_empty()
+            && verify(&reference, &instance)
+                .iter()
+                .all(|violation| self.objective.penalises(violation));
+        let warm_start = if feasible {
+            Some(reference)
+        } else {
+            // Insert every added aircraft before the first aircraft in the sequence that was scheduled after its
+            // release time
+            added.sort_by_key(|&flight_idx| instance.flights()[flight_idx].release_time());
+            let mut added = added.into_iter().peekable();
+            let mut order = Vec::with_capacity(instance.flights().len());
+            for sched in &reference {
+                while let Some(flight_idx) = added.next_if(|&flight_idx| {
+                    instance.flights()[flight_idx].release_time() < sched.flight_time()
+                }) {
+                    order.push(flight_idx);
+                }
+                order.push(sched.flight_index());
+            }
+            order.extend(added);
+
+            decode(&order, &instance, &self.objective).ok()
+        };
+
+        let solver = Self {
+            warm_start: warm_start.map(WarmStart::Sequence),
+            ..self.clone()
+        };
+        let solution = solver.solve(&instance)?;
+        Ok((solution, instance))
+    }
+
+    fn solver_info(&self) -> SolverInfo {
+        let horizon = match self.horizon.map(|horizon| horizon.window) {
+            None => "none".to_owned(),
+            Some(Window::Flights(size)) => size.to_string(),
+            Some(Window::Duration(duration)) => format!("{:?}", duration),
+        };
+        let deice_strategy = match self.deice_strategy {
+            DeiceStrategy::ByTobt => "by-tobt",
+            DeiceStrategy::ByCtot => "by-ctot",
+            DeiceStrategy::Integrated => "integrated",
+        };
+
+        let mut settings = BTreeMap::from([
+            ("horizon".to_owned(), horizon),
+            ("deice-strategy".to_owned(), deice_strategy.to_owned()),
+        ]);
+        if let Some(horizon) = &self.horizon {
+            settings.insert("horizon-commit".to_owned(), horizon.commit.to_string());
+            settings.insert("horizon-overlap".to_owned(), horizon.overlap.to_string());
+        }
+        if let Some(time_limit) = self.time_limit {
+            settings.insert("time-limit".to_owned(), format!("{:?}", time_limit));
+        }
+        if let Some(node_limit) = self.node_limit {
+            settings.insert("node-limit".to_owned(), node_limit.to_string());
+        }
+        if let Some(threads) = self.threads {
+            settings.insert("threads".to_owned(), threads.to_string());
+        }
+        match self.strategy {
+            SearchStrategy::DepthFirst => {
+                settings.insert("search-strategy".to_owned(), "depth-first".to_owned());
+            },
+            SearchStrategy::BestFirst => {
+                settings.insert("search-strategy".to_owned(), "best-first".to_owned());
+            },
+            SearchStrategy::LimitedDiscrepancy { discrepancies } => {
+                settings.insert(
+                    "search-strategy".to_owned(),
+                    "limited-discrepancy".to_owned(),
+                );
+                settings.insert("discrepancies".to_owned(), discrepancies.to_string());
+            },
+            SearchStrategy::Beam { width } => {
+                settings.insert("search-strategy".to_owned(), "beam".to_owned());
+                settings.insert("beam-width".to_owned(), width.to_string());
             },
-            DeiceStrategy::ByCtot => {
-                decomposed::branch_bound_rolling(instance, self.horizon, |dep, other| {
-                    match dep.ctot.as_ref().zip(other.ctot.as_ref()) {
-                        Some((dep_ctot, other_ctot)) => {
-                            dep_ctot.earliest().cmp(&other_ctot.earliest())
-                        },
-                        None => dep.tobt.cmp(&other.tobt),
-                    }
-                })
+        }
+        let lower_bound = match self.lower_bound {
+            LowerBound::Simple => "simple",
+            LowerBound::Tight => "tight",
+        };
+        settings.insert("lower-bound".to_owned(), lower_bound.to_owned());
+        match &self.warm_start {
+            None => {},
+            Some(WarmStart::Sequence(_)) => {
+                settings.insert("warm-start".to_owned(), "sequence".to_owned());
+            },
+            Some(WarmStart::UpperBound(upper_bound)) => {
+                settings.insert("warm-start".to_owned(), "upper-bound".to_owned());
+                settings.insert("upper-bound".to_owned(), upper_bound.to_string());
             },
-            DeiceStrategy::Integrated => integrated::branch_bound_rolling(instance, self.horizon),
         }
+        if self.objective != Objective::default() {
+            settings.insert("objective".to_owned(), format!("{:?}", self.objective));
+        }
+
+        SolverInfo {
+            name: "branch-bound".to_owned(),
+            settings,
+        }
+    }
+}
+
+impl Solve for BranchBound {
+    fn solve(&self, instance: &Instance) -> Result<Solution, SolveError> {
+        let start = Instant::now();
+
+        let (incumbent, upper_bound) = match &self.warm_start {
+            None => (None, None),
+            Some(WarmStart::Sequence(schedules)) => {
+                // NOTE: Constraints such as taking off after the CTOT slot are penalised rather than forbidden by the
+                //       objective, so only the other constraints need to be satisfied.
+                let mut violations = verify(schedules, instance);
+                violations.retain(|violation| !self.objective.penalises(violation));
+                if !violations.is_empty() {
+                    return Err(SolveError::InfeasibleWarmStart { violations });
+                }
+
+                let mut positions = vec![0; instance.flights().len()];
+                for (position, sched) in schedules.iter().enumerate() {
+                    positions[sched.flight_index()] = position;
+                }
+                (Some(positions), None)
+            },
+            Some(WarmStart::UpperBound(upper_bound)) => (None, Some(*upper_bound)),
+        };
+
+        let options = SearchOptions {
+            objective: &self.objective,
+            strategy: self.strategy,
+            lower_bound: self.lower_bound,
+            limits: Limits::new(
+                self.time_limit.map(|time_limit| start + time_limit),
+                self.node_limit,
+                self.cancel_token.as_ref(),
+            ),
+            threads: self.threads.unwrap_or(NonZeroUsize::MIN),
+            incumbent,
+            upper_bound,
+        };
+
+        let schedules = match self.deice_strategy {
+            DeiceStrategy::ByTobt => decomposed::branch_bound_rolling(
+                instance,
+                self.horizon.as_ref(),
+                &options,
+                |dep, other| dep.tobt.cmp(&other.tobt),
+            ),
+            DeiceStrategy::ByCtot => decomposed::branch_bound_rolling(
+                instance,
+                self.horizon.as_ref(),
+                &options,
+                |dep, other| match dep.ctot.as_ref().zip(other.ctot.as_ref()) {
+                    Some((dep_ctot, other_ctot)) => dep_ctot.earliest().cmp(&other_ctot.earliest()),
+                    None => dep.tobt.cmp(&other.tobt),
+                },
+            ),
+            DeiceStrategy::Integrated => {
+                integrated::branch_bound_rolling(instance, self.horizon.as_ref(), &options)
+            },
+        }?;
+
+        // NOTE: Only integrated de-icing without a rolling horizon (or with one that covers every aircraft) explores
+        //       every possible sequence, since decomposed de-icing fixes the de-icing queue beforehand.
+        //       Integrated de-icing also assigns every departure to the de-icing pad that becomes free the earliest,
+        //       which is only guaranteed to be the best choice if there is a single de-icing pad.
+        //       Every aircraft is also scheduled as early as possible, which is only guaranteed to be the best choice
+        //       if the objective never rewards landing or taking off later.
+        //       Limited discrepancy and beam search skip parts of the search tree, and stopping early because of a
+        //       limit means that not every sequence was explored either.
+        let full_horizon = match self.horizon {
+            None => true,
+            Some(horizon) => {
+                let pinned = search::pinned_prefix(
+                    instance,
+                    &self.objective,
+                    &instance.reference_positions(),
+                );
+                pinned.len() + rolling::window_size(&horizon, instance, &pinned)
+                    >= instance.flights().len()
+            },
+        };
+        let exhaustive = match self.strategy {
+            SearchStrategy::DepthFirst | SearchStrategy::BestFirst => true,
+            SearchStrategy::LimitedDiscrepancy { .. } | SearchStrategy::Beam { .. } => false,
+        };
+        let optimal = full_horizon
+            && exhaustive
+            && self.deice_strategy == DeiceStrategy::Integrated
+            && instance.deice_pads().len() == 1
+            && !self.objective.rewards_later_times()
+            && !options.limits.is_reached();
+
+        let mut solution = Solution::new(schedules, instance);
+        solution.solver = Some(self.solver_info());
+        solution.wall_time = Some(start.elapsed());
+        solution.nodes = Some(options.limits.nodes());
+        solution.optimal = optimal;
+
+        Ok(solution)
     }
 }
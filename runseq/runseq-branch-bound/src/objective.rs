@@ -0,0 +1,279 @@
+use std::time::Duration;
+
+use runseq_instance::{schedule::Schedule, verify::Violation, Instance};
+
+use crate::cost::{self, Cost, CostBreakdown, Deviations};
+
+/// The objective function minimised by the [`BranchBound`](crate::BranchBound) solver.
+///
+/// The objective is made up of several terms, each of which penalises a different kind of deviation from an ideal
+/// schedule.
+/// The default objective penalises squared delay and squared lateness after Calculated Take-Off Time (CTOT) slots, and
+/// uses squared runway hold only to break ties between sequences with the same objective value.
+///
+/// Aircraft are always scheduled to land or take off as early as possible, so penalties for taking off early in a
+/// CTOT slot are part of the objective value, but can only be reduced by changing the order in which aircraft are
+/// sequenced rather than by holding a departure back until its target CTOT.
+///
+/// Time windows, Holdover Times (HOTs), and maximum runway hold durations are hard constraints by default.
+/// Each of them can instead be made a soft constraint by giving it a penalty, in which case a sequence that violates it
+/// is penalised rather than discarded, and the violations are reported in
+/// [`Solution::violations`](runseq_instance::solution::Solution::violations).
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Objective {
+    /// The penalty for the time between an aircraft's base time and its scheduled landing or take-off time.
+    pub delay: Term,
+    /// The penalty for the time between the end of a departure's CTOT slot and its scheduled take-off time.
+    pub ctot_late: Term,
+    /// The penalty for the time between a departure's scheduled take-off time and its target CTOT, if it takes off
+    /// before its target CTOT.
+    ///
+    /// This is part of the objective value and is reported as [`Cost::ctot_early`].
+    /// No departure is ever held back to take off closer to its target CTOT, so the penalty can only be reduced by
+    /// sequencing departures differently.
+    pub ctot_early: Term,
+    /// The penalty for the time a de-iced departure spends holding at the runway.
+    pub runway_hold: Term,
+    /// Whether the runway hold penalty is part of the objective value, or only used to break ties between sequences
+    /// with the same objective value.
+    pub runway_hold_in_objective: bool,
+    /// The penalty for an aircraft deviating from its schedule in the [reference sequence](Instance::reference) of an
+    /// instance.
+    ///
+    /// Aircraft that are not part of the reference sequence, including those of instances without one, are never
+    /// penalised.
+    pub stability: Term,
+    /// How the deviation of an aircraft from the reference sequence is measured.
+    pub stability_shift: Shift,
+    /// The penalty for the time between the end of an aircraft's time window and its scheduled landing or take-off
+    /// time, if time windows are soft constraints.
+    pub window_late: Option<Term>,
+    /// The penalty for the time between the expiry of a de-iced departure's HOT and its scheduled take-off time, if
+    /// HOTs are soft constraints.
+    ///
+    /// Departures are only de-iced early enough for their HOT to expire if there is no other way to schedule them.
+    pub hot_excess: Option<Term>,
+    /// The penalty for the time a de-iced departure spends holding at the runway beyond the maximum runway hold
+    /// duration of the instance, if the maximum runway hold duration is a soft constraint.
+    ///
+    /// This is on top of the penalty for [`Objective::runway_hold`].
+    pub runway_hold_excess: Option<Term>,
+}
+
+impl Default for Objective {
+    fn default() -> Self {
+        Self {
+            delay: Term::new(1, Penalty::Squared),
+            ctot_late: Term::new(1, Penalty::Squared),
+            ctot_early: Term::new(0, Penalty::Linear),
+            runway_hold: Term::new(1, Penalty::Squared),
+            runway_hold_in_objective: false,
+            stability: Term::new(0, Penalty::Linear),
+            stability_shift: Shift::Time,
+            window_late: None,
+            hot_excess: None,
+            runway_hold_excess: None,
+        }
+    }
+}
+
+impl Objective {
+    /// Calculates the value of this objective for a runway sequence.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if any of the schedules refer to aircraft that are not in the instance.
+    pub fn solution_cost(&self, solution: &[Schedule], instance: &Instance) -> Cost {
+        let reference_positions = instance.reference_positions();
+        solution
+            .iter()
+            .enumerate()
+            .map(|(position, sched)| {
+                cost::schedule_cost(sched, position, instance, self, &reference_positions)
+            })
+            .sum()
+    }
+
+    /// Calculates the value of this objective for each aircraft in a runway sequence, along with the total value for
+    /// the sequence.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if any of the schedules refer to aircraft that are not in the instance.
+    pub fn cost_breakdown(&self, solution: &[Schedule], instance: &Instance) -> CostBreakdown {
+        CostBreakdown::new(solution, instance, self)
+    }
+
+    /// Checks if a violated constraint is penalised by this objective rather than forbidden outright.
+    ///
+    /// This is the case for departures taking off after their CTOT slot (see [`Violation::is_soft`]), as well as for
+    /// aircraft landing or taking off after their time window, departures taking off after their HOT has expired, and
+    /// departures holding at the runway for too long, if the objective has a penalty for them.
+    pub fn penalises(&self, violation: &Violation) -> bool {
+        match violation {
+            Violation::OutsideWindow { time, latest, .. } => {
+                self.window_late.is_some() && time > latest
+            },
+            Violation::Hot { .. } => self.hot_excess.is_some(),
+            Violation::RunwayHold { .. } => self.runway_hold_excess.is_some(),
+            _ => violation.is_soft(),
+        }
+    }
+
+    // Checks if landing or taking off later than possible can ever be cheaper, in which case scheduling every aircraft
+    // as early as possible is not guaranteed to produce an optimal sequence
+    pub(crate) fn rewards_later_times(&self) -> bool {
+        self.ctot_early.weight > 0
+            || (self.stability.weight > 0 && self.stability_shift == Shift::Time)
+    }
+
+    pub(crate) fn cost(&self, deviations: &Deviations) -> Cost {
+        let stability = match self.stability_shift {
+            Shift::Time => self.stability.penalise(deviations.time_shift),
+            Shift::Position => self.stability.penalise_units(deviations.position_shift),
+        };
+        let violation = [
+            (&self.window_late, deviations.window_late),
+            (&self.hot_excess, deviations.hot_excess),
+            (&self.runway_hold_excess, deviations.runway_hold_excess),
+        ]
+        .into_iter()
+        .filter_map(|(term, deviation)| Some(term.as_ref()?.penalise(deviation)))
+        .fold(0, u64::saturating_add);
+        Cost::new(
+            self.delay.penalise(deviations.delay),
+            self.ctot_late.penalise(deviations.ctot_late),
+            self.ctot_early.penalise(deviations.ctot_early),
+            self.runway_hold.penalise(deviations.runway_hold),
+            stability,
+            violation,
+            self.runway_hold_in_objective,
+        )
+    }
+}
+
+/// How the deviation of an aircraft from its schedule in a reference sequence is measured by [`Objective::stability`].
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub enum Shift {
+    /// The time between the aircraft's landing or take-off time and its landing or take-off time in the reference
+    /// sequence.
+    #[default]
+    Time,
+    /// The number of positions the aircraft has moved by from its position in the reference sequence.
+    ///
+    /// Every position counts as one second of deviation, including for the segments of a [`Penalty::Piecewise`]
+    /// penalty.
+    Position,
+}
+
+/// A single weighted term of an [`Objective`].
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Term {
+    /// The weight the penalty is multiplied by.
+    pub weight: u64,
+    /// How the penalty grows with the deviation it penalises.
+    pub penalty: Penalty,
+}
+
+impl Term {
+    /// Creates a new term with the given weight and penalty.
+    pub fn new(weight: u64, penalty: Penalty) -> Self {
+        Self { weight, penalty }
+    }
+
+    /// Calculates the weighted penalty for a deviation.
+    pub fn penalise(&self, deviation: Duration) -> u64 {
+        self.penalise_units(deviation.as_secs())
+    }
+
+    pub(crate) fn penalise_units(&self, units: u64) -> u64 {
+        match self.weight {
+            0 => 0,
+            weight => weight.saturating_mul(self.penalty.penalise_units(units)),
+        }
+    }
+}
+
+/// How the penalty of an [`Objective`] [`Term`] grows with the deviation it penalises, in seconds.
+///
+/// Penalties saturate at [`u64::MAX`] rather than overflowing.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Penalty {
+    /// The penalty is the deviation itself.
+    Linear,
+    /// The penalty is the square of the deviation.
+    Squared,
+    /// The penalty grows linearly at a different rate in each segment of the deviation.
+    Piecewise(Piecewise),
+}
+
+impl Penalty {
+    /// Calculates the penalty for a deviation.
+    pub fn penalise(&self, deviation: Duration) -> u64 {
+        self.penalise_units(deviation.as_secs())
+    }
+
+    fn penalise_units(&self, secs: u64) -> u64 {
+        match self {
+            Self::Linear => secs,
+            Self::Squared => secs.saturating_pow(2),
+            Self::Piecewise(piecewise) => piecewise.penalise_units(secs),
+        }
+    }
+}
+
+/// A penalty that grows linearly at a different rate in each of several [`Segment`]s of the deviation.
+///
+/// The penalty is zero before the first segment starts.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Piecewise {
+    segments: Vec<Segment>,
+}
+
+impl Piecewise {
+    /// Creates a new piecewise penalty from its segments, returning [`None`] if the segments are not sorted by their
+    /// start or if two segments start at the same deviation.
+    pub fn new<S>(segments: S) -> Option<Self>
+    where
+        S: Into<Vec<Segment>>,
+    {
+        let segments = segments.into();
+        segments
+            .windows(2)
+            .all(|pair| pair[0].start < pair[1].start)
+            .then_some(Self { segments })
+    }
+
+    /// Extracts a slice of all segments of the penalty, sorted by their start.
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    fn penalise_units(&self, secs: u64) -> u64 {
+        let ends = self
+            .segments
+            .iter()
+            .skip(1)
+            .map(|segment| segment.start.as_secs())
+            .chain([u64::MAX]);
+        self.segments
+            .iter()
+            .zip(ends)
+            .map(|(segment, end)| {
+                let covered = secs.min(end).saturating_sub(segment.start.as_secs());
+                segment.rate.saturating_mul(covered)
+            })
+            .fold(0, u64::saturating_add)
+    }
+}
+
+/// A segment of a [`Piecewise`] penalty.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Segment {
+    /// The deviation at which the segment starts.
+    ///
+    /// The segment ends where the next segment starts.
+    pub start: Duration,
+    /// The penalty per second of deviation within the segment.
+    pub rate: u64,
+}
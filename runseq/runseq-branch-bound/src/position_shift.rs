@@ -0,0 +1,46 @@
+use runseq_instance::Instance;
+
+use crate::search::Node;
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct PositionShift {
+    max_shift: usize,
+    fcfs_positions: Vec<usize>,
+}
+
+impl PositionShift {
+    pub fn new(instance: &Instance) -> Option<Self> {
+        let max_shift = instance.max_position_shift?;
+        Some(Self {
+            max_shift,
+            fcfs_positions: instance.fcfs_positions(),
+        })
+    }
+
+    // Checks if an aircraft can be sequenced at the given position without moving further from its
+    // first-come-first-served position than allowed
+    pub fn within(&self, flight_idx: usize, depth: usize) -> bool {
+        self.fcfs_positions[flight_idx].abs_diff(depth) <= self.max_shift
+    }
+
+    // Checks if an aircraft can be sequenced next after a partial sequence, without it or any aircraft that remains to
+    // be sequenced moving further from its first-come-first-served position than allowed
+    pub fn allows(&self, flight_idx: usize, sequence: &[Node]) -> bool {
+        let depth = sequence.len();
+        if !self.within(flight_idx, depth) {
+            return false;
+        }
+
+        // NOTE: Every aircraft up to this first-come-first-served position can no longer be sequenced after the next
+        //       position, so all of them must either be sequenced already or be the aircraft sequenced next.
+        let Some(last_due) = depth.checked_sub(self.max_shift) else {
+            return true;
+        };
+        let due = sequence
+            .iter()
+            .filter(|node| self.fcfs_positions[node.sched.flight_index()] <= last_due)
+            .count()
+            + usize::from(self.fcfs_positions[flight_idx] <= last_due);
+        due > last_due
+    }
+}
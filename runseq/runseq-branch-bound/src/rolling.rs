@@ -0,0 +1,111 @@
+use std::{num::NonZeroUsize, ops::Range};
+
+use runseq_instance::{schedule::Schedule, solve::SolveError, Instance};
+
+use crate::{
+    search::{BranchBoundState, Node},
+    RollingHorizon,
+    Window,
+};
+
+pub fn rolling_horizon<S>(
+    instance: &Instance,
+    horizon: Option<&RollingHorizon>,
+    state: &mut BranchBoundState,
+    mut search_window: S,
+) -> Result<Vec<Schedule>, SolveError>
+where
+    S: FnMut(&mut BranchBoundState, Range<usize>) -> Result<(), SolveError>,
+{
+    let flight_count = instance.flights().len();
+
+    // NOTE: Without a rolling horizon, the entire sequence is searched as a single window.
+    let horizon = horizon
+        .copied()
+        .unwrap_or_else(|| RollingHorizon::flights(NonZeroUsize::MAX));
+    let complete_order_sets = state.complete_order_sets.clone();
+
+    // NOTE: Aircraft with pinned schedules are fixed from the start, and are never sequenced again.
+    let pinned = state.current_solution.len();
+    let mut fixed = pinned;
+    loop {
+        let start = fixed.saturating_sub(horizon.overlap).max(pinned);
+        let end = fixed
+            .saturating_add(window_size(&horizon, instance, &state.current_solution))
+            .min(flight_count);
+
+        // Re-open the most recently fixed aircraft, so that they are sequenced again along with the rest of the window
+        state.current_solution.truncate(start);
+
+        // Remove all fixed aircraft from the sets of complete orders
+        let mut is_fixed = vec![false; flight_count];
+        for node in &state.current_solution {
+            is_fixed[node.sched.flight_index()] = true;
+        }
+        state.complete_order_sets = complete_order_sets
+            .iter()
+            .map(|set| {
+                set.iter()
+                    .copied()
+                    .filter(|&flight_idx| !is_fixed[flight_idx])
+                    .collect()
+            })
+            .collect();
+        state.next_in_complete_order_sets.fill(0);
+
+        search_window(state, start..end)?;
+
+        if end == flight_count {
+            break;
+        }
+
+        // Fix the first aircraft in the window that were not fixed before, and move on to the next window
+        // NOTE: This cannot fail, since the window would have returned an error if it had no solution.
+        let committed = (fixed + horizon.commit.get()).min(end);
+        let nodes = state
+            .best_solution
+            .drain(..committed - start)
+            .collect::<Vec<_>>();
+        state.current_solution.extend(nodes);
+        fixed = committed;
+    }
+
+    let solution = state
+        .current_solution
+        .drain(..)
+        .chain(state.best_solution.drain(..))
+        .map(|node| node.sched)
+        .collect();
+    Ok(solution)
+}
+
+// Finds the number of aircraft that have not been fixed yet to sequence in the next window
+pub fn window_size(horizon: &RollingHorizon, instance: &Instance, fixed: &[Node]) -> usize {
+    match horizon.window {
+        Window::Flights(size) => size.get(),
+        Window::Duration(duration) => {
+            let mut is_fixed = vec![false; instance.flights().len()];
+            for node in fixed {
+                is_fixed[node.sched.flight_index()] = true;
+            }
+            let releases = instance
+                .flights()
+                .iter()
+                .enumerate()
+                .filter(|&(flight_idx, _)| !is_fixed[flight_idx])
+                .map(|(_, flight)| flight.release_time())
+                .collect::<Vec<_>>();
+
+            let Some(&earliest) = releases.iter().min() else {
+                return 0;
+            };
+            let size = releases
+                .iter()
+                .filter(|&&release| release < earliest + duration)
+                .count();
+
+            // NOTE: Every window sequences at least one aircraft, even if the duration is zero.
+            size.max(1)
+        },
+    }
+}
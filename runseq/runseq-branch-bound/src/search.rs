@@ -1,16 +1,33 @@
-use std::{ops::Range, time::Duration};
+use std::{
+    cmp::{self, Reverse},
+    collections::{BTreeMap, BinaryHeap, HashMap},
+    num::NonZeroUsize,
+    ops::Range,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
 
 use chrono::NaiveDateTime;
 
 use runseq_instance::{
     flight::{Flight, TimeWindow},
+    pin::Pin,
     schedule::Schedule,
+    solve::{CancelToken, Constraint, InfeasibleFlight, SolveError},
     Instance,
 };
 
 use crate::{
     complete_orders::separation_identical_complete_orders,
-    cost::{arrival_cost, departure_cost, estimated_remaining_cost, Cost},
+    cost::{arrival_cost, departure_cost, estimated_remaining_cost, schedule_cost, Cost},
+    objective::Objective,
+    position_shift::PositionShift,
+    LowerBound,
+    SearchStrategy,
 };
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
@@ -27,92 +44,1027 @@ pub struct BranchBoundState {
     pub next_in_complete_order_sets: Vec<usize>,
     pub current_solution: Vec<Node>,
     pub best_solution: Vec<Node>,
+    pub position_shift: Option<PositionShift>,
+    pub soft: SoftConstraints,
+    pub reference_positions: Vec<Option<usize>>,
 }
 
 impl BranchBoundState {
-    pub fn new(instance: &Instance) -> Self {
-        let complete_order_sets = separation_identical_complete_orders(instance);
+    pub fn new(instance: &Instance, objective: &Objective) -> Self {
+        let complete_order_sets = separation_identical_complete_orders(instance, objective);
         let next_in_complete_order_sets = vec![0; complete_order_sets.len()];
 
-        let current_solution = Vec::with_capacity(instance.flights().len());
-        let best_solution = current_solution.clone();
-
         Self {
             complete_order_sets,
             next_in_complete_order_sets,
+            ..Self::without_complete_orders(instance, objective)
+        }
+    }
+
+    // Creates a state whose current solution only contains the aircraft with pinned schedules, for solvers that only
+    // use the current solution when expanding aircraft and so leave the complete-order sets empty
+    pub fn without_complete_orders(instance: &Instance, objective: &Objective) -> Self {
+        let reference_positions = instance.reference_positions();
+        let mut current_solution = Vec::with_capacity(instance.flights().len());
+        current_solution.extend(pinned_prefix(instance, objective, &reference_positions));
+        let best_solution = Vec::with_capacity(instance.flights().len());
+
+        Self {
+            complete_order_sets: Vec::new(),
+            next_in_complete_order_sets: Vec::new(),
             current_solution,
             best_solution,
+            position_shift: PositionShift::new(instance),
+            soft: SoftConstraints::new(objective),
+            reference_positions,
+        }
+    }
+}
+
+// The constraints that are penalised by the objective rather than forbidden outright, which are never used to discard
+// schedules
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct SoftConstraints {
+    pub windows: bool,
+    pub hot: bool,
+    pub runway_hold: bool,
+}
+
+impl SoftConstraints {
+    pub fn new(objective: &Objective) -> Self {
+        Self {
+            windows: objective.window_late.is_some(),
+            hot: objective.hot_excess.is_some(),
+            runway_hold: objective.runway_hold_excess.is_some(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Limits<'a> {
+    deadline: Option<Instant>,
+    node_limit: Option<u64>,
+    cancel_token: Option<&'a CancelToken>,
+    nodes: AtomicU64,
+    reached: AtomicBool,
+}
+
+impl<'a> Limits<'a> {
+    pub fn new(
+        deadline: Option<Instant>,
+        node_limit: Option<u64>,
+        cancel_token: Option<&'a CancelToken>,
+    ) -> Self {
+        Self {
+            deadline,
+            node_limit,
+            cancel_token,
+            nodes: AtomicU64::new(0),
+            reached: AtomicBool::new(false),
+        }
+    }
+
+    pub fn is_reached(&self) -> bool {
+        self.reached.load(Ordering::Relaxed)
+    }
+
+    pub fn nodes(&self) -> u64 {
+        self.nodes.load(Ordering::Relaxed)
+    }
+
+    // NOTE: Once a limit is reached, it stays reached for the rest of the search, even across rolling horizon windows.
+    //       Nodes are counted across all threads, and only once they are explored, so that the node limit is never
+    //       exceeded.
+    fn visit(&self) -> bool {
+        let reached = self.is_reached()
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+            || self.cancel_token.is_some_and(CancelToken::is_cancelled)
+            || self
+                .nodes
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |nodes| {
+                    match self.node_limit {
+                        Some(limit) if nodes >= limit => None,
+                        _ => Some(nodes + 1),
+                    }
+                })
+                .is_err();
+        if reached {
+            self.reached.store(true, Ordering::Relaxed);
+        }
+        reached
+    }
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Rejections {
+    depth: usize,
+    flights: BTreeMap<usize, Constraint>,
+}
+
+impl Rejections {
+    // NOTE: Only the rejections at the deepest point in the search are kept, since those are the aircraft that
+    //       prevented the most complete sequence from being completed.
+    pub fn record(&mut self, depth: usize, flight_index: usize, constraint: Constraint) {
+        if depth > self.depth {
+            self.depth = depth;
+            self.flights.clear();
+        }
+        if depth == self.depth {
+            self.flights.insert(flight_index, constraint);
+        }
+    }
+
+    fn merge(&mut self, other: Rejections) {
+        for (flight_index, constraint) in other.flights {
+            self.record(other.depth, flight_index, constraint);
         }
     }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn into_infeasible_flights(self) -> Vec<InfeasibleFlight> {
+        self.flights
+            .into_iter()
+            .map(|(flight_index, constraint)| InfeasibleFlight {
+                flight_index,
+                constraint,
+            })
+            .collect()
+    }
+}
+
+// NOTE: The search tree is split into more subtrees than there are threads, since subtrees can vary greatly in size
+//       and threads that finish early can then pick up the remaining subtrees.
+const SUBTREES_PER_THREAD: usize = 8;
+
+#[derive(Debug)]
+pub struct SearchOptions<'a> {
+    pub objective: &'a Objective,
+    pub strategy: SearchStrategy,
+    pub lower_bound: LowerBound,
+    pub limits: Limits<'a>,
+    pub threads: NonZeroUsize,
+    // The position of every aircraft in a known sequence, if any
+    pub incumbent: Option<Vec<usize>>,
+    pub upper_bound: Option<u64>,
+}
+
+// How the de-icing times of departures are decided, which determines how early they can finish de-icing
+#[derive(Debug, Copy, Clone)]
+pub enum Deicing<'a> {
+    Integrated,
+    Decomposed(&'a HashMap<usize, (NaiveDateTime, usize)>),
 }
 
-pub fn branch_bound<E, I>(
+pub fn branch_bound<E>(
     instance: &Instance,
     state: &mut BranchBoundState,
-    nodes: &mut Vec<Node>,
-    expand: &mut E,
+    expand: &E,
+    deicing: Deicing<'_>,
     window: Range<usize>,
-) where
-    E: FnMut(&Flight, usize, &Instance, &BranchBoundState) -> I,
-    I: IntoIterator<Item = Schedule>,
+    options: &SearchOptions<'_>,
+) -> Result<(), SolveError>
+where
+    E: Fn(&Flight, usize, &Instance, &BranchBoundState) -> Result<Vec<Schedule>, Constraint> + Sync,
 {
-    let mut current_cost = Cost::default();
-    let mut best_cost = Cost::MAX;
+    // NOTE: The upper bound applies to the cost of the entire sequence, so the cost of the aircraft that have already
+    //       been fixed in earlier windows is subtracted from it.
+    let fixed_cost = state.current_solution[..window.start]
+        .iter()
+        .map(|node| node.cost)
+        .sum::<Cost>();
+    let upper_bound = options
+        .upper_bound
+        .map(|upper_bound| Cost::upper_bound(upper_bound.saturating_sub(fixed_cost.as_u64())));
 
-    // Initialise the queue with initial states, taken from first aircraft in each complete-order set
-    nodes.extend(branches(instance, state, expand));
+    let mut search = Search {
+        instance,
+        expand,
+        deicing,
+        window: window.clone(),
+        objective: options.objective,
+        strategy: options.strategy,
+        lower_bound: options.lower_bound,
+        upper_bound,
+        limits: &options.limits,
+        discrepancy_limited: AtomicBool::new(false),
+    };
 
-    while let Some(node) = nodes.pop() {
-        // Reset the cost and complete-order sets to match the current depth
-        for removed in state.current_solution.drain(node.depth..) {
-            state.next_in_complete_order_sets[removed.complete_order_idx] -= 1;
-            current_cost -= removed.cost;
+    let mut rejections = Rejections::default();
+
+    // Follow the known sequence through the current window to find an initial best sequence, if possible
+    let incumbent = options
+        .incumbent
+        .as_ref()
+        .and_then(|positions| search.project(state, positions));
+
+    // Split the search tree into subtrees, ordered by when a depth-first search would explore them
+    // NOTE: Best-first and beam search compare nodes across the entire search tree, so they always search it as a
+    //       whole on a single thread.
+    let threads = match options.strategy {
+        SearchStrategy::DepthFirst | SearchStrategy::LimitedDiscrepancy { .. } => {
+            options.threads.get()
+        },
+        SearchStrategy::BestFirst | SearchStrategy::Beam { .. } => 1,
+    };
+    let subtrees = match threads {
+        1 => vec![Subtree::default()],
+        threads => search.split(
+            &mut state.clone(),
+            &mut rejections,
+            threads * SUBTREES_PER_THREAD,
+        ),
+    };
+
+    let mut best = search.search(
+        state,
+        &subtrees,
+        threads,
+        incumbent.clone(),
+        &mut rejections,
+    );
+
+    // Search again without the upper bound if it was too tight to find any sequence within it, which can happen with a
+    // rolling horizon even if there is a sequence within the upper bound
+    if best.is_none() && search.upper_bound.is_some() && !options.limits.is_reached() {
+        search.upper_bound = None;
+        best = search.search(state, &subtrees, threads, incumbent, &mut rejections);
+    }
+
+    // Fall back to the first sequence that a depth-first search would try if a limit was reached before finding any
+    // sequence, so that the windows fixed so far are kept and the rest of the aircraft are still sequenced
+    if best.is_none() && options.limits.is_reached() {
+        best = search.dive(state);
+    }
+
+    // Reset the current solution since we only need the best one
+    state.current_solution.drain(window.start..);
+    state.best_solution = best.map(|(_, solution)| solution).unwrap_or_default();
+
+    // NOTE: A window that was stopped early without finding any sequence may still have one, so it is not reported as
+    //       infeasible.
+    match state.best_solution.len() == window.len() {
+        true => Ok(()),
+        false if options.limits.is_reached() => Err(SolveError::LimitReached {
+            window,
+            nodes: options.limits.nodes(),
+        }),
+        false => Err(SolveError::Infeasible {
+            window,
+            flights: rejections.into_infeasible_flights(),
+        }),
+    }
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+struct Subtree {
+    path: Vec<Node>,
+    discrepancies: usize,
+    // The number of ancestors of the root of the subtree that are also ancestors of the root of the previous subtree
+    shared_ancestors: usize,
+}
+
+struct Candidate {
+    lower_bound: Cost,
+    order: usize,
+    cost: Cost,
+    path: Vec<Node>,
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.lower_bound
+            .cmp(&other.lower_bound)
+            .then_with(|| self.order.cmp(&other.order))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Candidate {}
+
+struct SubtreeResult {
+    bound: Option<Cost>,
+    discrepancies: Option<usize>,
+    best: Option<(Cost, Vec<Node>)>,
+    rejections: Rejections,
+}
+
+struct Progress {
+    results: Vec<Option<SubtreeResult>>,
+    committed: usize,
+    committing: bool,
+    best: Option<(Cost, Vec<Node>)>,
+    rejections: Rejections,
+    // Whether a depth-first search would explore each ancestor of the root of the last committed subtree
+    explored_ancestors: Vec<bool>,
+}
+
+impl Progress {
+    fn new(subtrees: usize, best: Option<(Cost, Vec<Node>)>) -> Self {
+        Self {
+            results: (0..subtrees).map(|_| None).collect(),
+            committed: 0,
+            committing: false,
+            best,
+            rejections: Rejections::default(),
+            explored_ancestors: Vec::new(),
+        }
+    }
+
+    fn best_cost(&self) -> Option<Cost> {
+        self.best.as_ref().map(|(cost, _)| *cost)
+    }
+
+    // NOTE: Sequences found in earlier subtrees that have not been committed yet are likely to remain the best known
+    //       sequences once they are, so they are used to prune the search as well.
+    fn bound(&self, ordinal: usize) -> Option<Cost> {
+        self.results[self.committed.min(ordinal)..ordinal]
+            .iter()
+            .flatten()
+            .filter_map(|result| result.best.as_ref().map(|(cost, _)| *cost))
+            .chain(self.best_cost())
+            .min()
+    }
+
+    fn commit(&mut self, result: SubtreeResult) {
+        self.rejections.merge(result.rejections);
+        if let Some((cost, solution)) = result.best {
+            let improved = match self.best_cost() {
+                None => true,
+                Some(best_cost) => cost < best_cost,
+            };
+            if improved {
+                self.best = Some((cost, solution));
+            }
         }
+        self.committed += 1;
+    }
+}
+
+struct Search<'a, E> {
+    instance: &'a Instance,
+    expand: &'a E,
+    deicing: Deicing<'a>,
+    window: Range<usize>,
+    objective: &'a Objective,
+    strategy: SearchStrategy,
+    lower_bound: LowerBound,
+    upper_bound: Option<Cost>,
+    limits: &'a Limits<'a>,
+    discrepancy_limited: AtomicBool,
+}
+
+impl<E> Search<'_, E>
+where
+    E: Fn(&Flight, usize, &Instance, &BranchBoundState) -> Result<Vec<Schedule>, Constraint> + Sync,
+{
+    fn branches(&self, state: &BranchBoundState, rejections: &mut Rejections) -> Vec<Node> {
+        let mut nodes = branches(
+            self.instance,
+            state,
+            self.expand,
+            self.objective,
+            rejections,
+        )
+        .collect::<Vec<_>>();
+
+        // NOTE: Since the last added node is explored first, the nodes are reversed to match the order they are
+        //       explored in.
+        nodes.reverse();
+        nodes
+    }
 
-        // Ignore the node and its sub-nodes if the lower bound is worse than the best known cost
-        if current_cost + node.cost >= best_cost {
-            continue;
+    // Resets the current solution to the start of the window, and then follows a path of nodes from there
+    fn follow(&self, state: &mut BranchBoundState, path: &[Node]) -> Cost {
+        for removed in state.current_solution.drain(self.window.start..) {
+            state.next_in_complete_order_sets[removed.complete_order_idx] -= 1;
         }
 
-        let last_sched = node.sched.clone();
+        let mut cost = Cost::default();
+        for node in path {
+            cost += node.cost;
+            state.next_in_complete_order_sets[node.complete_order_idx] += 1;
+            state.current_solution.push(node.clone());
+        }
+        cost
+    }
 
-        // Update the cost and complete order sets, and add the aircraft to the current solution
-        current_cost += node.cost;
+    // Estimates the cost of the remaining aircraft after adding a node to the current solution
+    fn estimate(&self, state: &mut BranchBoundState, node: &Node) -> Cost {
         state.next_in_complete_order_sets[node.complete_order_idx] += 1;
-        state.current_solution.push(node);
+        state.current_solution.push(node.clone());
+        let estimated_cost = self.estimated_remaining_cost(state, &node.sched);
+        state.current_solution.pop();
+        state.next_in_complete_order_sets[node.complete_order_idx] -= 1;
+        estimated_cost
+    }
+
+    fn estimated_remaining_cost(&self, state: &BranchBoundState, last_sched: &Schedule) -> Cost {
+        estimated_remaining_cost(
+            self.instance,
+            state,
+            last_sched,
+            self.objective,
+            self.lower_bound,
+            self.deicing,
+        )
+    }
+
+    // Finds the cost that sequences must be better than, given the best known cost
+    fn best_cost(&self, bound: Option<Cost>) -> Cost {
+        bound
+            .into_iter()
+            .chain(self.upper_bound)
+            .min()
+            .unwrap_or(Cost::MAX)
+    }
+
+    // Checks whether a depth-first search would prune the last node of a path, given the best known cost
+    fn prunes(&self, state: &mut BranchBoundState, path: &[Node], bound: Option<Cost>) -> bool {
+        let cost = self.follow(state, path);
+        let Some(last) = path.last() else {
+            return false;
+        };
+        cost + self.estimated_remaining_cost(state, &last.sched) >= self.best_cost(bound)
+    }
+
+    fn is_complete(&self, path: &[Node]) -> bool {
+        self.window.start + path.len() == self.window.end
+    }
+
+    // Follows a known sequence through the search tree, always choosing the aircraft that comes first in it and
+    // re-timing it as cheaply as possible, until the window is complete
+    fn project(
+        &self,
+        state: &mut BranchBoundState,
+        positions: &[usize],
+    ) -> Option<(Cost, Vec<Node>)> {
+        self.greedy(state, |branches| {
+            branches
+                .into_iter()
+                .min_by_key(|node| (positions[node.sched.flight_index()], node.cost))
+        })
+    }
+
+    // Follows the first sub-node of every node through the search tree until the window is complete, without pruning
+    // or counting any nodes
+    fn dive(&self, state: &mut BranchBoundState) -> Option<(Cost, Vec<Node>)> {
+        self.greedy(state, |branches| branches.into_iter().next())
+    }
+
+    // Follows a single path through the search tree, choosing one of the sub-nodes of every node, until the window is
+    // complete or no sub-node is chosen
+    fn greedy<C>(&self, state: &mut BranchBoundState, mut choose: C) -> Option<(Cost, Vec<Node>)>
+    where
+        C: FnMut(Vec<Node>) -> Option<Node>,
+    {
+        let mut path = Vec::new();
+        let mut cost = Cost::default();
+        self.follow(state, &[]);
+        while !self.is_complete(&path) {
+            let Some(node) = choose(self.branches(state, &mut Rejections::default())) else {
+                break;
+            };
+            cost += node.cost;
+            state.next_in_complete_order_sets[node.complete_order_idx] += 1;
+            state.current_solution.push(node.clone());
+            path.push(node);
+        }
+        self.follow(state, &[]);
+
+        self.is_complete(&path).then_some((cost, path))
+    }
+
+    fn search(
+        &self,
+        state: &mut BranchBoundState,
+        subtrees: &[Subtree],
+        threads: usize,
+        best: Option<(Cost, Vec<Node>)>,
+        rejections: &mut Rejections,
+    ) -> Option<(Cost, Vec<Node>)> {
+        match self.strategy {
+            SearchStrategy::LimitedDiscrepancy { discrepancies } => {
+                let mut best = best;
+                for discrepancies in 0..=discrepancies {
+                    self.discrepancy_limited.store(false, Ordering::Relaxed);
+                    best = self.search_subtrees(
+                        state,
+                        subtrees,
+                        threads,
+                        best,
+                        Some(discrepancies),
+                        rejections,
+                    );
 
-        // If we have sequenced all aircraft according to the rolling horizon size, then update the best solution.
-        // We know that this solution will definitely be the best solution since it is a full solution and since the
-        // costs are compared above.
-        if state.current_solution.len() == window.end {
-            best_cost = current_cost;
-            state.best_solution = state.current_solution[window.clone()].to_vec();
-            continue;
+                    // Stop early if no nodes were skipped for having too many discrepancies, since the entire search
+                    // tree was then explored
+                    if !self.discrepancy_limited.load(Ordering::Relaxed) {
+                        break;
+                    }
+                }
+                best
+            },
+            _ => self.search_subtrees(state, subtrees, threads, best, None, rejections),
         }
+    }
+
+    fn split(
+        &self,
+        state: &mut BranchBoundState,
+        rejections: &mut Rejections,
+        count: usize,
+    ) -> Vec<Subtree> {
+        let mut subtrees = vec![Subtree::default()];
+
+        // Keep expanding every subtree by one level until there are enough subtrees, or until they cannot be expanded
+        // any further because they are complete sequences
+        while subtrees.len() < count
+            && !subtrees
+                .iter()
+                .all(|subtree| self.is_complete(&subtree.path))
+        {
+            subtrees = subtrees
+                .into_iter()
+                .flat_map(|subtree| {
+                    if self.is_complete(&subtree.path) {
+                        return vec![subtree];
+                    }
 
-        // Ignore the node and its sub-nodes if the current lower bound plus the estimated cost for the
-        // remaining aircraft is worse than the best known cost
-        if current_cost + estimated_remaining_cost(instance, state, &last_sched) >= best_cost {
-            continue;
+                    self.follow(state, &subtree.path);
+                    let branches = self.branches(state, rejections);
+                    let first = branches.first().map(|node| node.sched.flight_index());
+                    branches
+                        .into_iter()
+                        .enumerate()
+                        .map(|(branch_idx, node)| {
+                            let discrepancy = Some(node.sched.flight_index()) != first;
+                            let mut path = subtree.path.clone();
+                            path.push(node);
+                            // NOTE: The subtree of the first sub-node follows the subtree before the one being
+                            //       expanded, while the subtree of every later sub-node follows the subtrees of the
+                            //       sub-nodes before it.
+                            let shared_ancestors = match branch_idx {
+                                0 => subtree.shared_ancestors,
+                                _ => subtree.path.len(),
+                            };
+                            Subtree {
+                                path,
+                                discrepancies: subtree.discrepancies + usize::from(discrepancy),
+                                shared_ancestors,
+                            }
+                        })
+                        .collect()
+                })
+                .collect();
         }
 
-        // Expand the node and add its sub-nodes to the queue
-        nodes.extend(branches(instance, state, expand));
+        subtrees
     }
 
-    // Reset the current solution since we only need the best one
-    state.current_solution.drain(window.start..);
+    fn search_subtrees(
+        &self,
+        state: &mut BranchBoundState,
+        subtrees: &[Subtree],
+        threads: usize,
+        best: Option<(Cost, Vec<Node>)>,
+        discrepancies: Option<usize>,
+        rejections: &mut Rejections,
+    ) -> Option<(Cost, Vec<Node>)> {
+        // Search the subtrees, with each thread taking the next unexplored subtree once it finishes its current one
+        let next_subtree = AtomicUsize::new(0);
+        let progress = Mutex::new(Progress::new(subtrees.len(), best));
+        let worker = |state: &mut BranchBoundState| {
+            let mut nodes = Vec::with_capacity(self.instance.flights().len());
+            loop {
+                let ordinal = next_subtree.fetch_add(1, Ordering::Relaxed);
+                let Some(subtree) = subtrees.get(ordinal) else {
+                    break;
+                };
+                let bound = progress.lock().unwrap().bound(ordinal);
+                let result = self.search_subtree(state, &mut nodes, subtree, bound, discrepancies);
+                self.commit(&progress, subtrees, ordinal, result, state, &mut nodes);
+            }
+        };
+        match threads {
+            1 => worker(state),
+            threads => {
+                let state = &*state;
+                thread::scope(|scope| {
+                    for _ in 0..threads {
+                        scope.spawn(|| worker(&mut state.clone()));
+                    }
+                });
+            },
+        }
+
+        let progress = progress.into_inner().unwrap();
+        rejections.merge(progress.rejections);
+        progress.best
+    }
+
+    fn search_subtree(
+        &self,
+        state: &mut BranchBoundState,
+        nodes: &mut Vec<(Node, usize)>,
+        subtree: &Subtree,
+        bound: Option<Cost>,
+        discrepancies: Option<usize>,
+    ) -> SubtreeResult {
+        let mut rejections = Rejections::default();
+
+        let best = match self.strategy {
+            SearchStrategy::DepthFirst | SearchStrategy::LimitedDiscrepancy { .. } => {
+                self.depth_first(state, nodes, subtree, bound, discrepancies, &mut rejections)
+            },
+            SearchStrategy::BestFirst => self.best_first(state, subtree, bound, &mut rejections),
+            SearchStrategy::Beam { width } => {
+                self.beam(state, subtree, bound, width, &mut rejections)
+            },
+        };
+
+        SubtreeResult {
+            bound,
+            discrepancies,
+            best,
+            rejections,
+        }
+    }
+
+    fn depth_first(
+        &self,
+        state: &mut BranchBoundState,
+        nodes: &mut Vec<(Node, usize)>,
+        subtree: &Subtree,
+        bound: Option<Cost>,
+        discrepancies: Option<usize>,
+        rejections: &mut Rejections,
+    ) -> Option<(Cost, Vec<Node>)> {
+        let mut best_cost = self.best_cost(bound);
+        let mut best_solution = None;
+
+        // Follow the path to the root of the subtree, or start from the root of the entire search tree
+        let mut current_cost = match subtree.path.split_last() {
+            None => {
+                self.follow(state, &[]);
+                self.push_branches(state, nodes, 0, discrepancies, rejections);
+                Cost::default()
+            },
+            Some(_)
+                if discrepancies
+                    .is_some_and(|discrepancies| subtree.discrepancies > discrepancies) =>
+            {
+                self.discrepancy_limited.store(true, Ordering::Relaxed);
+                return None;
+            },
+            Some((root, path)) => {
+                let cost = self.follow(state, path);
+                nodes.push((root.clone(), subtree.discrepancies));
+                cost
+            },
+        };
+
+        while let Some((node, node_discrepancies)) = nodes.pop() {
+            // Stop searching once a limit is reached
+            if self.limits.visit() {
+                break;
+            }
+
+            // Reset the cost and complete-order sets to match the current depth
+            for removed in state.current_solution.drain(node.depth..) {
+                state.next_in_complete_order_sets[removed.complete_order_idx] -= 1;
+                current_cost = current_cost.saturating_sub(removed.cost);
+            }
+
+            // Ignore the node and its sub-nodes if the lower bound is worse than the best known cost
+            if current_cost + node.cost >= best_cost {
+                continue;
+            }
+
+            let last_sched = node.sched.clone();
+
+            // Update the cost and complete order sets, and add the aircraft to the current solution
+            current_cost += node.cost;
+            state.next_in_complete_order_sets[node.complete_order_idx] += 1;
+            state.current_solution.push(node);
+
+            // If we have sequenced all aircraft according to the rolling horizon size, then update the best solution.
+            // We know that this solution will definitely be the best solution since it is a full solution and since the
+            // costs are compared above.
+            if state.current_solution.len() == self.window.end {
+                best_cost = current_cost;
+                best_solution = Some(state.current_solution[self.window.clone()].to_vec());
+                continue;
+            }
+
+            // Ignore the node and its sub-nodes if the current lower bound plus the estimated cost for the
+            // remaining aircraft is worse than the best known cost
+            let estimated_cost = self.estimated_remaining_cost(state, &last_sched);
+            if current_cost + estimated_cost >= best_cost {
+                continue;
+            }
+
+            // Expand the node and add its sub-nodes to the queue
+            self.push_branches(state, nodes, node_discrepancies, discrepancies, rejections);
+        }
+
+        // Discard any nodes left over if the search was stopped early
+        nodes.clear();
+
+        best_solution.map(|solution| (best_cost, solution))
+    }
+
+    fn push_branches(
+        &self,
+        state: &BranchBoundState,
+        nodes: &mut Vec<(Node, usize)>,
+        node_discrepancies: usize,
+        discrepancies: Option<usize>,
+        rejections: &mut Rejections,
+    ) {
+        let branches = branches(
+            self.instance,
+            state,
+            self.expand,
+            self.objective,
+            rejections,
+        );
+        match discrepancies {
+            None => nodes.extend(branches.map(|node| (node, node_discrepancies))),
+            Some(discrepancies) => {
+                // Every sub-node that sequences a different aircraft than the one explored first counts as a
+                // discrepancy from the order that the sub-nodes are sorted in
+                // NOTE: The other schedules of the aircraft explored first, such as its other runways or de-icing
+                //       times, follow the same order and so are not counted as discrepancies.
+                let branches = branches.collect::<Vec<_>>();
+                let first = branches.last().map(|node| node.sched.flight_index());
+                for node in branches {
+                    let discrepancy = Some(node.sched.flight_index()) != first;
+                    let node_discrepancies = node_discrepancies + usize::from(discrepancy);
+                    match node_discrepancies <= discrepancies {
+                        true => nodes.push((node, node_discrepancies)),
+                        false => self.discrepancy_limited.store(true, Ordering::Relaxed),
+                    }
+                }
+            },
+        }
+    }
+
+    fn best_first(
+        &self,
+        state: &mut BranchBoundState,
+        subtree: &Subtree,
+        bound: Option<Cost>,
+        rejections: &mut Rejections,
+    ) -> Option<(Cost, Vec<Node>)> {
+        let mut best_cost = self.best_cost(bound);
+        let mut best_solution = None;
+
+        // NOTE: Ties between nodes with the same lower bound are broken by the order they were added in, so that the
+        //       search is deterministic.
+        let mut queue = BinaryHeap::from([Reverse(Candidate {
+            lower_bound: Cost::default(),
+            order: 0,
+            cost: self.follow(state, &subtree.path),
+            path: subtree.path.clone(),
+        })]);
+        let mut order = 1;
+
+        while let Some(Reverse(candidate)) = queue.pop() {
+            // Stop searching once a limit is reached
+            if self.limits.visit() {
+                break;
+            }
+
+            // Stop once every remaining node has a lower bound that is worse than the best known cost, since the nodes
+            // are explored in order of their lower bounds
+            if candidate.lower_bound >= best_cost {
+                break;
+            }
+
+            self.follow(state, &candidate.path);
+
+            // Expand the node and add its sub-nodes to the queue, unless they are worse than the best known cost
+            for node in self.branches(state, rejections) {
+                let cost = candidate.cost + node.cost;
+                if cost >= best_cost {
+                    continue;
+                }
+
+                let mut path = candidate.path.clone();
+                if self.window.start + path.len() + 1 == self.window.end {
+                    path.push(node);
+                    best_cost = cost;
+                    best_solution = Some(path);
+                    continue;
+                }
+
+                let lower_bound = cost + self.estimate(state, &node);
+                if lower_bound >= best_cost {
+                    continue;
+                }
+
+                path.push(node);
+                queue.push(Reverse(Candidate {
+                    lower_bound,
+                    order,
+                    cost,
+                    path,
+                }));
+                order += 1;
+            }
+        }
+
+        best_solution.map(|solution| (best_cost, solution))
+    }
+
+    fn beam(
+        &self,
+        state: &mut BranchBoundState,
+        subtree: &Subtree,
+        bound: Option<Cost>,
+        width: NonZeroUsize,
+        rejections: &mut Rejections,
+    ) -> Option<(Cost, Vec<Node>)> {
+        let mut best_cost = self.best_cost(bound);
+        let mut best_solution = None;
+
+        let mut beam = vec![Candidate {
+            lower_bound: Cost::default(),
+            order: 0,
+            cost: self.follow(state, &subtree.path),
+            path: subtree.path.clone(),
+        }];
+
+        // Expand every node in the beam one level at a time, only keeping the nodes with the best lower bounds
+        'search: while !beam.is_empty() {
+            let mut candidates = Vec::new();
+            for candidate in beam {
+                // Stop searching once a limit is reached
+                if self.limits.visit() {
+                    break 'search;
+                }
+
+                self.follow(state, &candidate.path);
+
+                for node in self.branches(state, rejections) {
+                    let cost = candidate.cost + node.cost;
+                    if cost >= best_cost {
+                        continue;
+                    }
+
+                    let mut path = candidate.path.clone();
+                    if self.window.start + path.len() + 1 == self.window.end {
+                        path.push(node);
+                        best_cost = cost;
+                        best_solution = Some(path);
+                        continue;
+                    }
+
+                    let lower_bound = cost + self.estimate(state, &node);
+                    if lower_bound >= best_cost {
+                        continue;
+                    }
+
+                    path.push(node);
+                    candidates.push(Candidate {
+                        lower_bound,
+                        order: candidates.len(),
+                        cost,
+                        path,
+                    });
+                }
+            }
+
+            candidates.sort_unstable();
+            candidates.truncate(width.get());
+            beam = candidates;
+        }
+
+        best_solution.map(|solution| (best_cost, solution))
+    }
+
+    // NOTE: Subtrees are committed in the order a sequential depth-first search would explore them in, and any
+    //       subtree that was searched with a different best known cost than a sequential search would have had at that
+    //       point is searched again. With a rolling horizon, the lower bound also accounts for aircraft outside the
+    //       window and can prune sequences that are better within the window, so this is needed for the parallel
+    //       search to always find the same sequence as the sequential search.
+    //       For the same reason, the ancestors of every subtree are checked against the best known cost that a
+    //       sequential search would have had when it first reached them, and a subtree below a pruned ancestor is
+    //       discarded.
+    fn commit(
+        &self,
+        progress: &Mutex<Progress>,
+        subtrees: &[Subtree],
+        ordinal: usize,
+        result: SubtreeResult,
+        state: &mut BranchBoundState,
+        nodes: &mut Vec<(Node, usize)>,
+    ) {
+        let mut progress_guard = progress.lock().unwrap();
+        progress_guard.results[ordinal] = Some(result);
+
+        // Only one thread commits subtrees at a time, and it commits any subtrees finished by other threads meanwhile
+        if progress_guard.committing {
+            return;
+        }
+        progress_guard.committing = true;
+
+        loop {
+            let committed = progress_guard.committed;
+            let Some(result) = progress_guard
+                .results
+                .get_mut(committed)
+                .and_then(Option::take)
+            else {
+                break;
+            };
+            let bound = progress_guard.best_cost();
+
+            // A sequential search first reaches the ancestors that a subtree does not share with the previous one right
+            // before searching it, so it checks them against the best known cost at this point
+            let subtree = &subtrees[committed];
+            let ancestors = subtree.path.len().saturating_sub(1);
+            let explored_ancestors = &mut progress_guard.explored_ancestors;
+            explored_ancestors.truncate(subtree.shared_ancestors);
+            while explored_ancestors.len() < ancestors {
+                let depth = explored_ancestors.len();
+                let explored = explored_ancestors.last().copied().unwrap_or(true)
+                    && !self.prunes(state, &subtree.path[..=depth], bound);
+                explored_ancestors.push(explored);
+            }
+            if !explored_ancestors.last().copied().unwrap_or(true) {
+                progress_guard.commit(SubtreeResult {
+                    best: None,
+                    ..result
+                });
+                continue;
+            }
+
+            // A subtree searched with a better best known cost than a sequential search would have had may have
+            // pruned too much, so it is always searched again. A subtree searched with a worse best known cost explores
+            // every node that it would have explored otherwise, so it only needs to be searched again if it found a
+            // better sequence than the best known one.
+            // NOTE: Any rejections are discarded once a sequence is found, so it does not matter that they may differ.
+            let improved = |bound| match &result.best {
+                None => false,
+                Some((cost, _)) => *cost < bound,
+            };
+            let valid = match (result.bound, bound) {
+                (None, None) => true,
+                (None, Some(bound)) => !improved(bound),
+                (Some(result_bound), Some(bound)) => match result_bound.cmp(&bound) {
+                    cmp::Ordering::Less => false,
+                    cmp::Ordering::Equal => true,
+                    cmp::Ordering::Greater => !improved(bound),
+                },
+                (Some(_), None) => false,
+            };
+            let result = match valid || self.limits.is_reached() {
+                true => result,
+                false => {
+                    drop(progress_guard);
+                    let result =
+                        self.search_subtree(state, nodes, subtree, bound, result.discrepancies);
+                    progress_guard = progress.lock().unwrap();
+                    result
+                },
+            };
+
+            progress_guard.commit(result);
+        }
+
+        progress_guard.committing = false;
+    }
 }
 
-fn branches<'a, E, I>(
+fn branches<'a, E>(
     instance: &'a Instance,
     state: &'a BranchBoundState,
-    expand: &'a mut E,
+    expand: &'a E,
+    objective: &'a Objective,
+    rejections: &'a mut Rejections,
 ) -> impl Iterator<Item = Node> + 'a
 where
-    E: FnMut(&Flight, usize, &Instance, &BranchBoundState) -> I,
-    I: IntoIterator<Item = Schedule> + 'a,
+    E: Fn(&Flight, usize, &Instance, &BranchBoundState) -> Result<Vec<Schedule>, Constraint>,
 {
+    let depth = state.current_solution.len();
+
     let latest_release = state
         .current_solution
         .iter()
@@ -124,6 +1076,7 @@ where
 
     // Ignore aircraft whose latest times are before the latest release time in the current solution, since
     // that would mean that the aircraft cannot be sequenced anyways and should have been sequenced before
+    // NOTE: Neither this nor the rule below applies to soft time windows, which aircraft can always be scheduled after.
     let mut next_flights = state
         .complete_order_sets
         .iter()
@@ -134,13 +1087,24 @@ where
             let flight_idx = complete_order_set.get(next_in_set_idx).copied()?;
             let flight = &instance.flights()[flight_idx];
 
+            if state
+                .position_shift
+                .as_ref()
+                .is_some_and(|shift| !shift.allows(flight_idx, &state.current_solution))
+            {
+                rejections.record(depth, flight_idx, Constraint::PositionShift);
+                return None;
+            }
+
             match latest_release {
                 None => Some((flight, flight_idx, complete_order_idx)),
                 Some(latest_release)
-                    if flight
-                        .window()
-                        .is_some_and(|window| window.latest() <= latest_release) =>
+                    if !state.soft.windows
+                        && flight
+                            .window()
+                            .is_some_and(|window| window.latest() <= latest_release) =>
                 {
+                    rejections.record(depth, flight_idx, Constraint::Window);
                     None
                 },
                 Some(_) => Some((flight, flight_idx, complete_order_idx)),
@@ -160,7 +1124,7 @@ where
         .filter_map(|(flight, ..)| flight.window())
         .map(|window| window.latest())
         .min();
-    if let Some(next_latest) = next_latest {
+    if let Some(next_latest) = next_latest.filter(|_| !state.soft.windows) {
         next_flights.retain(|(flight, ..)| match flight.window() {
             None => true,
             Some(window) => window.earliest <= next_latest,
@@ -174,28 +1138,61 @@ where
                //       aircraft, and is thus potentially the best. Reversing the iterator ensures that it is
                //       the first node to be explored.
         .flat_map(move |(flight, flight_idx, complete_order_idx)| {
-            expand(flight, flight_idx, instance, state)
-                .into_iter()
-                .map(move |sched| {
-                    let cost = match (&sched, flight) {
-                        (Schedule::Arr(sched), Flight::Arr(arr)) => arrival_cost(sched, arr),
-                        (Schedule::Dep(sched), Flight::Dep(dep)) => departure_cost(sched, dep),
-                        // PANICS: This case will never be reached, because none of the expansion
-                        //         functions will ever schedule a departure when meant to be scheduling
-                        //         an arrival and vice-versa.
-                        _ => unreachable!(),
-                    };
-
-                    Node {
-                        sched,
-                        depth: state.current_solution.len(),
-                        complete_order_idx,
-                        cost,
-                    }
-                })
+            let reference_positions = &state.reference_positions;
+            let scheds = expand(flight, flight_idx, instance, state).unwrap_or_else(|constraint| {
+                rejections.record(depth, flight_idx, constraint);
+                Vec::new()
+            });
+            scheds.into_iter().map(move |sched| {
+                let cost = match (&sched, flight) {
+                    (Schedule::Arr(sched), Flight::Arr(arr)) => {
+                        arrival_cost(sched, depth, arr, instance, objective, reference_positions)
+                    },
+                    (Schedule::Dep(sched), Flight::Dep(dep)) => {
+                        departure_cost(sched, depth, dep, instance, objective, reference_positions)
+                    },
+                    // PANICS: This case will never be reached, because none of the expansion
+                    //         functions will ever schedule a departure when meant to be scheduling
+                    //         an arrival and vice-versa.
+                    _ => unreachable!(),
+                };
+
+                Node {
+                    sched,
+                    depth,
+                    complete_order_idx,
+                    cost,
+                }
+            })
         })
 }
 
+// Sequences the aircraft with pinned schedules before every other aircraft, in the order of their landing or take-off
+// times
+pub fn pinned_prefix(
+    instance: &Instance,
+    objective: &Objective,
+    reference_positions: &[Option<usize>],
+) -> Vec<Node> {
+    let mut scheds = instance
+        .pinned()
+        .iter()
+        .filter_map(Pin::as_schedule)
+        .collect::<Vec<_>>();
+    scheds.sort_by_key(|sched| sched.flight_time());
+
+    scheds
+        .into_iter()
+        .enumerate()
+        .map(|(depth, sched)| Node {
+            sched: sched.clone(),
+            depth,
+            complete_order_idx: 0,
+            cost: schedule_cost(sched, depth, instance, objective, reference_positions),
+        })
+        .collect()
+}
+
 // NOTE: Helper function to iterate between two `NaiveDateTime`s, since they do not impl `Step` which is
 //       still an unstable trait.
 pub fn iter_minutes(
@@ -209,9 +1206,113 @@ pub fn iter_minutes(
     (0..=diff).map(move |minute| from + Duration::from_secs(minute * 60))
 }
 
-pub fn within_window(time: NaiveDateTime, window: Option<&TimeWindow>) -> bool {
+pub fn separation_end(
+    instance: &Instance,
+    state: &BranchBoundState,
+    flight_index: usize,
+    runway: usize,
+) -> NaiveDateTime {
+    state
+        .current_solution
+        .iter()
+        .rev()
+        .map(|node| {
+            // NOTE: Aircraft on independent runways do not need to be separated, but are still scheduled in the order
+            //       they are sequenced in so that the sequence is always ordered by landing or take-off time.
+            let sep = instance
+                .separation(
+                    node.sched.flight_index(),
+                    node.sched.runway(),
+                    flight_index,
+                    runway,
+                )
+                .unwrap_or(Duration::ZERO);
+            node.sched.flight_time() + sep
+        })
+        .max()
+        .unwrap_or(NaiveDateTime::MIN)
+}
+
+pub fn deice_pad_ends(instance: &Instance, state: &BranchBoundState) -> Vec<Option<NaiveDateTime>> {
+    let mut pad_ends = vec![None; instance.deice_pads().len()];
+
+    // NOTE: Pinned de-icing slots block their de-icing pads from the start, even if their departures have not been
+    //       sequenced yet.
+    let sequenced = state.current_solution.iter().filter_map(|node| {
+        let sched = node.sched.as_departure()?;
+        Some((sched.flight_index, sched.deice?, sched.deice_pad))
+    });
+    let pinned = instance.pinned().iter().filter_map(|pin| {
+        let (deice, deice_pad) = pin.deice()?;
+        Some((pin.flight_index(), deice, deice_pad))
+    });
+    let deices = sequenced
+        .chain(pinned)
+        .filter_map(|(flight_idx, deice, deice_pad)| {
+            let params = instance.flights()[flight_idx]
+                .as_departure()?
+                .deice
+                .as_ref()?;
+            Some((
+                deice_pad,
+                deice + instance.deice_duration(params, deice_pad),
+            ))
+        });
+    for (pad, deice_end) in deices {
+        let pad_end = &mut pad_ends[pad];
+        *pad_end = (*pad_end).max(Some(deice_end));
+    }
+    pad_ends
+}
+
+pub fn feasible<I, T>(candidates: I) -> Result<Vec<T>, Constraint>
+where
+    I: IntoIterator<Item = Result<Vec<T>, Constraint>>,
+{
+    // NOTE: An aircraft without any candidates at all has no runways it can use, so it cannot be scheduled within
+    //       its time window either.
+    //       Otherwise, the most specific constraint across all runways is reported, so that the reason does not depend
+    //       on the order the runways are numbered in.
+    let mut rejection = Constraint::Window;
+    let mut feasible = Vec::new();
+    for candidate in candidates {
+        match candidate {
+            Ok(scheds) => feasible.extend(scheds),
+            Err(constraint) if specificity(constraint) > specificity(rejection) => {
+                rejection = constraint
+            },
+            Err(_) => {},
+        }
+    }
+
+    match feasible.is_empty() {
+        true => Err(rejection),
+        false => Ok(feasible),
+    }
+}
+
+// Ranks constraints by how much they narrow down why an aircraft cannot be scheduled, since every constraint other than
+// the time window is only checked once the aircraft can otherwise be scheduled within it
+fn specificity(constraint: Constraint) -> u8 {
+    match constraint {
+        Constraint::Window => 0,
+        Constraint::RunwayHold => 1,
+        Constraint::Hot => 2,
+        Constraint::DeiceSlot => 3,
+        Constraint::PositionShift => 4,
+    }
+}
+
+pub fn within_window(
+    time: NaiveDateTime,
+    window: Option<&TimeWindow>,
+    soft: SoftConstraints,
+) -> bool {
     match window {
         None => true,
+        // NOTE: Aircraft are never scheduled before their time windows, since their release times are never before
+        //       the start of their time windows.
+        Some(_) if soft.windows => true,
         Some(window) => window.as_range().contains(&time),
     }
 }
@@ -9,7 +9,7 @@ use chrono::{NaiveDateTime, ParseError};
 use thiserror::Error;
 
 use runseq_instance::{
-    flight::{Arrival, Deice, Departure, Flight},
+    flight::{Arrival, Deice, Departure, Flight, FlightInfo},
     sep::SeparationsLenError,
     Instance,
 };
@@ -75,11 +75,12 @@ pub fn from_furini_with_limit(
 fn parse_flight(line: &str) -> Result<Flight, FromFuriniError> {
     let mut parts = line.split_ascii_whitespace();
 
-    // NOTE: We don't actually need any of this data, but have to parse it nonetheless since the
-    //       data is expected to be in a specific format.
-    let _registration = next_part(&mut parts)?;
-    let _model = next_part(&mut parts)?;
-    let _size_class = next_part(&mut parts)?;
+    let info = FlightInfo {
+        registration: Some(next_part(&mut parts)?.to_owned()),
+        aircraft_type: Some(next_part(&mut parts)?.to_owned()),
+        weight_class: Some(next_part(&mut parts)?.to_owned()),
+        ..FlightInfo::default()
+    };
 
     let kind = next_part(&mut parts)?;
 
@@ -98,6 +99,8 @@ fn parse_flight(line: &str) -> Result<Flight, FromFuriniError> {
             earliest_time,
             base_time: earliest_time,
             window: None,
+            runways: None,
+            info: Some(info),
         })),
         "D" => Ok(Flight::Dep(Departure {
             earliest_time,
@@ -113,6 +116,8 @@ fn parse_flight(line: &str) -> Result<Flight, FromFuriniError> {
             lineup_duration: MINUTE * 5,
             window: None,
             ctot: None,
+            runways: None,
+            info: Some(info),
         })),
         kind => Err(FromFuriniError::InvalidKind(kind.to_owned())),
     }?;
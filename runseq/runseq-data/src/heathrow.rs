@@ -9,7 +9,8 @@ use itertools::Itertools;
 use thiserror::Error;
 
 use runseq_instance::{
-    flight::{Ctot, Deice, Departure, Flight},
+    flight::{Ctot, Deice, Departure, Flight, FlightInfo},
+    runway::Runway,
     Instance,
 };
 
@@ -19,7 +20,7 @@ use flight::{DeiceStatus, FlightId, FlightRow, ParseDeiceStatusError, ParseWeigh
 mod sep;
 use sep::{create_separation_matrix, parse_separation_configs, SeparationConfigs};
 
-const DATETIME_FMT: &'static str = "%Y-%m-%d %H:%M:%S";
+const DATETIME_FMT: &str = "%Y-%m-%d %H:%M:%S";
 
 const MINUTE: Duration = Duration::from_secs(60);
 
@@ -83,6 +84,25 @@ where
         .map(|(_, group)| {
             let flight_rows = group.take(flight_limit).collect::<Vec<_>>();
             let separations = create_separation_matrix(&flight_rows, separation_configs);
+
+            // NOTE: Aircraft without a runway are allowed to use any runway, and a single unnamed runway is used if
+            //       none of the aircraft have runways.
+            let runway_ids = flight_rows
+                .iter()
+                .filter_map(|flight| flight.runway_id)
+                .unique()
+                .sorted_by_key(|runway_id| runway_id.0)
+                .collect::<Vec<_>>();
+            let runways = match runway_ids.is_empty() {
+                true => vec![Runway::default()],
+                false => runway_ids
+                    .iter()
+                    .map(|runway_id| Runway {
+                        name: Some(runway_id.0.to_owned()),
+                    })
+                    .collect(),
+            };
+
             let flights = flight_rows
                 .into_iter()
                 .map(|flight| {
@@ -118,6 +138,22 @@ where
                     }
                     earliest_time += taxi_duration + lineup_duration;
 
+                    let runways = flight.runway_id.map(|runway_id| {
+                        let runway_idx = runway_ids
+                            .iter()
+                            .position(|&other| other == runway_id)
+                            .unwrap();
+                        vec![runway_idx]
+                    });
+
+                    let info = FlightInfo {
+                        id: Some(flight.aircraft_id.0.to_owned()),
+                        weight_class: Some(flight.weight_class.as_str().to_owned()),
+                        stand: Some(flight.stand_id.0.to_owned()),
+                        route: Some(flight.route_id.0.to_owned()),
+                        ..FlightInfo::default()
+                    };
+
                     Flight::Dep(Departure {
                         earliest_time,
                         base_time: earliest_time,
@@ -128,10 +164,12 @@ where
                         lineup_duration,
                         window: None,
                         ctot,
+                        runways,
+                        info: Some(info),
                     })
                 })
                 .collect::<Vec<_>>();
-            Instance::new(flights, separations, MINUTE * 5).unwrap()
+            Instance::with_runways(flights, separations, runways, Vec::new(), MINUTE * 5).unwrap()
         })
         .collect::<Vec<_>>()
 }
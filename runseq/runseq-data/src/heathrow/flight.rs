@@ -14,11 +14,8 @@ pub struct FlightRow<'a> {
     pub route_id: RouteId<'a>,
     pub speed_group: SpeedGroup,
     pub weight_class: WeightClass,
-    pub atot: Option<NaiveDateTime>,
     pub ctot: Option<NaiveDateTime>,
     pub tobt: NaiveDateTime,
-    pub aobt: Option<NaiveDateTime>,
-    pub eczt: Option<NaiveDateTime>,
     pub deice_status: DeiceStatus,
 }
 
@@ -43,7 +40,9 @@ impl<'a> FlightRow<'a> {
 
         let weight_class = extract_field(&mut parts, "Weight Class")?.parse::<WeightClass>()?;
 
-        let atot = extract_opt_field(&mut parts, "ATOT")?
+        // NOTE: The actual take-off and off-block times and the ECZT are not used, but are still checked to be valid
+        //       times since the data is expected to be in a specific format.
+        extract_opt_field(&mut parts, "ATOT")?
             .map(|atot| NaiveDateTime::parse_from_str(atot, DATETIME_FMT))
             .transpose()?;
 
@@ -54,11 +53,11 @@ impl<'a> FlightRow<'a> {
         let tobt = extract_field(&mut parts, "TOBT")?;
         let tobt = NaiveDateTime::parse_from_str(tobt, DATETIME_FMT)?;
 
-        let aobt = extract_opt_field(&mut parts, "AOBT")?
+        extract_opt_field(&mut parts, "AOBT")?
             .map(|aobt| NaiveDateTime::parse_from_str(aobt, DATETIME_FMT))
             .transpose()?;
 
-        let eczt = extract_opt_field(&mut parts, "ECZT")?
+        extract_opt_field(&mut parts, "ECZT")?
             .map(|eczt| NaiveDateTime::parse_from_str(eczt, DATETIME_FMT))
             .transpose()?;
 
@@ -72,11 +71,8 @@ impl<'a> FlightRow<'a> {
             route_id: RouteId(route_id),
             speed_group: SpeedGroup(speed_group),
             weight_class,
-            atot,
             ctot,
             tobt,
-            aobt,
-            eczt,
             deice_status,
         })
     }
@@ -106,6 +102,18 @@ pub enum WeightClass {
     Upper,
 }
 
+impl WeightClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Small => "Small",
+            Self::Medium => "Medium",
+            Self::Heavy => "Heavy",
+            Self::Super => "Super",
+            Self::Upper => "Upper",
+        }
+    }
+}
+
 impl FromStr for WeightClass {
     type Err = ParseWeightClassError;
 
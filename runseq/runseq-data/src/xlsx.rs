@@ -17,6 +17,9 @@ use serde::Deserialize;
 use runseq_instance::{flight::Flight, Instance};
 
 /// Serializes an [`Instance`] as an Excel workbook.
+///
+/// The [identifying information](runseq_instance::flight::FlightInfo) of the aircraft is written to a separate sheet.
+/// This is an export only, since there is no way to read a workbook back into an [`Instance`].
 pub fn to_xlsx(instance: &Instance) -> Result<Workbook, XlsxError> {
     let mut workbook = Workbook::new();
 
@@ -30,6 +33,13 @@ pub fn to_xlsx(instance: &Instance) -> Result<Workbook, XlsxError> {
 
     workbook.push_worksheet(sheet);
 
+    let mut info_sheet = Worksheet::new();
+    info_sheet.set_name("Info")?;
+
+    write_flight_infos(instance, &mut info_sheet, &mut workbook)?;
+
+    workbook.push_worksheet(info_sheet);
+
     Ok(workbook)
 }
 
@@ -124,6 +134,33 @@ fn write_flights(
     Ok(())
 }
 
+fn write_flight_infos(
+    instance: &Instance,
+    sheet: &mut Worksheet,
+    workbook: &mut Workbook,
+) -> Result<(), XlsxError> {
+    sheet.deserialize_headers::<RawFlightInfo>(0, 0)?;
+    for (idx, flight) in instance.flights().iter().enumerate() {
+        let info = flight.info().cloned().unwrap_or_default();
+        let info = RawFlightInfo {
+            flight_index: idx as u64,
+            id: info.id,
+            callsign: info.callsign,
+            registration: info.registration,
+            aircraft_type: info.aircraft_type,
+            weight_class: info.weight_class,
+            stand: info.stand,
+            route: info.route,
+        };
+        info.write_to_sheet(idx as u32 + 1, 0, sheet)?;
+    }
+
+    let range = cell_range_absolute(1, 0, instance.flights().len() as u32, 7);
+    workbook.define_name("flightInfo", &format!("=Info!{}", range))?;
+
+    Ok(())
+}
+
 fn write_separations(
     instance: &Instance,
     sheet: &mut Worksheet,
@@ -188,7 +225,7 @@ struct RawFlight {
     ctot_allow_early: Option<u64>,
     #[serde(rename = "CTOT allowance after")]
     ctot_allow_late: Option<u64>,
-    #[serde(rename = "Earliest time")]
+    #[serde(rename = "Time window start")]
     window_earliest: Option<u64>,
     #[serde(rename = "Time window length")]
     window_length: Option<u64>,
@@ -206,7 +243,7 @@ impl RawFlight {
             FlightKind::Dep => "departure",
         };
         sheet
-            .write(row, col + 0, kind)?
+            .write(row, col, kind)?
             .write(row, col + 1, self.earliest_time)?
             .write(row, col + 2, self.base_time)?
             .write(row, col + 3, self.tobt)?
@@ -225,6 +262,46 @@ impl RawFlight {
     }
 }
 
+#[derive(Deserialize)]
+struct RawFlightInfo {
+    #[serde(rename = "Flight")]
+    flight_index: u64,
+    #[serde(rename = "ID")]
+    id: Option<String>,
+    #[serde(rename = "Callsign")]
+    callsign: Option<String>,
+    #[serde(rename = "Registration")]
+    registration: Option<String>,
+    #[serde(rename = "Aircraft type")]
+    aircraft_type: Option<String>,
+    #[serde(rename = "Weight class")]
+    weight_class: Option<String>,
+    #[serde(rename = "Stand")]
+    stand: Option<String>,
+    #[serde(rename = "Route")]
+    route: Option<String>,
+}
+
+impl RawFlightInfo {
+    fn write_to_sheet(
+        self,
+        row: RowNum,
+        col: ColNum,
+        sheet: &mut Worksheet,
+    ) -> Result<(), XlsxError> {
+        sheet
+            .write(row, col, self.flight_index)?
+            .write(row, col + 1, self.id)?
+            .write(row, col + 2, self.callsign)?
+            .write(row, col + 3, self.registration)?
+            .write(row, col + 4, self.aircraft_type)?
+            .write(row, col + 5, self.weight_class)?
+            .write(row, col + 6, self.stand)?
+            .write(row, col + 7, self.route)?;
+        Ok(())
+    }
+}
+
 #[derive(Deserialize)]
 enum FlightKind {
     Arr,
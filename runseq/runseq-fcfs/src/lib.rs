@@ -0,0 +1,102 @@
+//! A first-come-first-served (FCFS) heuristic for integrated runway sequencing and de-icing.
+
+#![deny(rust_2018_idioms)]
+#![warn(missing_docs)]
+
+use std::{collections::BTreeMap, time::Instant};
+
+use runseq_branch_bound::{decode, Objective};
+use runseq_instance::{
+    flight::Flight,
+    solution::{Solution, SolverInfo},
+    solve::{Solve, SolveError},
+    Instance,
+};
+
+/// A first-come-first-served solver for solving [`Instance`]s.
+///
+/// Aircraft are sequenced in the order they become ready, without ever being reordered.
+/// Each aircraft is then assigned the cheapest feasible landing or take-off time, runway, and de-icing time after the
+/// aircraft before it, according to the default [`Objective`] and using the same timing logic as
+/// [`BranchBound`](runseq_branch_bound::BranchBound) with integrated de-icing.
+///
+/// This is fast enough for any instance, and is mostly useful as a baseline to compare other solvers against or as a
+/// warm start for them.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Fcfs {
+    /// The order in which aircraft are sequenced.
+    ///
+    /// Only [`FcfsOrder::ReleaseTime`] is guaranteed to respect the
+    /// [`max_position_shift`](Instance::max_position_shift) of an instance.
+    pub order: FcfsOrder,
+}
+
+/// Different orders in which aircraft can be sequenced by the [`Fcfs`] solver.
+///
+/// Aircraft that are tied are sequenced in the order they appear in the instance.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub enum FcfsOrder {
+    /// Order by release time.
+    #[default]
+    ReleaseTime,
+    /// Order departures by Target Off-Block Time (TOBT).
+    ///
+    /// Arrivals do not have a TOBT, and are ordered by their release time instead.
+    Tobt,
+    /// Order departures by the earliest time of their Calculated Take-Off Time (CTOT) slot.
+    ///
+    /// Arrivals and departures without a CTOT are ordered by their release time instead.
+    Ctot,
+    /// Order by base time.
+    BaseTime,
+}
+
+impl Fcfs {
+    /// Returns the indices of the aircraft in an instance, in the order they are sequenced in.
+    pub fn sequence(&self, instance: &Instance) -> Vec<usize> {
+        let mut sequence = (0..instance.flights().len()).collect::<Vec<_>>();
+        sequence.sort_by_key(|&flight_idx| {
+            let flight = &instance.flights()[flight_idx];
+            match (self.order, flight) {
+                (FcfsOrder::ReleaseTime, _) => flight.release_time(),
+                (FcfsOrder::Tobt, Flight::Dep(dep)) => dep.tobt,
+                (FcfsOrder::Ctot, Flight::Dep(dep)) => match &dep.ctot {
+                    Some(ctot) => ctot.earliest(),
+                    None => dep.release_time(),
+                },
+                (FcfsOrder::Tobt | FcfsOrder::Ctot, Flight::Arr(arr)) => arr.release_time(),
+                (FcfsOrder::BaseTime, _) => flight.base_time(),
+            }
+        });
+        sequence
+    }
+
+    fn solver_info(&self) -> SolverInfo {
+        let order = match self.order {
+            FcfsOrder::ReleaseTime => "release-time",
+            FcfsOrder::Tobt => "tobt",
+            FcfsOrder::Ctot => "ctot",
+            FcfsOrder::BaseTime => "base-time",
+        };
+
+        SolverInfo {
+            name: "fcfs".to_owned(),
+            settings: BTreeMap::from([("order".to_owned(), order.to_owned())]),
+        }
+    }
+}
+
+impl Solve for Fcfs {
+    fn solve(&self, instance: &Instance) -> Result<Solution, SolveError> {
+        let start = Instant::now();
+
+        let sequence = self.sequence(instance);
+        let schedules = decode(&sequence, instance, &Objective::default())?;
+
+        let mut solution = Solution::new(schedules, instance);
+        solution.solver = Some(self.solver_info());
+        solution.wall_time = Some(start.elapsed());
+
+        Ok(solution)
+    }
+}
@@ -0,0 +1,24 @@
+//! De-icing pads and rigs.
+//!
+//! An [`Instance`](crate::Instance) always has at least one de-icing pad.
+//! Each pad can de-ice a single departure at a time, but departures on different pads can be de-iced simultaneously.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use serde_with::{serde_as, DurationSeconds};
+
+/// A de-icing pad or rig that departures can be de-iced at.
+#[serde_as] // NOTE: This must remain before the derives for `Serialize` and `Deserialize`.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct DeicePad {
+    /// The name of the de-icing pad, if any.
+    pub name: Option<String>,
+    /// The amount of time taken to apply de-icing fluid at this pad, if it differs from the de-icing duration of the
+    /// departures themselves.
+    #[serde_as(as = "Option<DurationSeconds>")]
+    #[serde(default)]
+    pub duration: Option<Duration>,
+}
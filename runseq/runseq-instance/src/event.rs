@@ -0,0 +1,346 @@
+//! Changes to the aircraft of an [`Instance`] during the day.
+//!
+//! Airport operations change every few minutes, as departures update their Target Off-Block Times (TOBTs), are issued
+//! or lose Calculated Take-Off Time (CTOT) slots, and aircraft are added or cancelled.
+//! Rather than building a new instance from scratch every time, an [`Event`] can be applied to an existing instance
+//! using [`Instance::apply`], which produces an [`Update`] containing the new instance along with a [`Remap`] from the
+//! aircraft indices of the old instance to those of the new one.
+
+use std::{cmp::Ordering, time::Duration};
+
+use chrono::NaiveDateTime;
+
+use serde::{Deserialize, Serialize};
+
+use serde_with::{serde_as, DurationSeconds};
+
+use thiserror::Error;
+
+use crate::{
+    flight::{Ctot, Deice, Departure, Flight},
+    pin::Pin,
+    schedule::Schedule,
+    sep::Separations,
+    validate,
+    Instance,
+};
+
+/// A change to the aircraft of an [`Instance`].
+#[serde_as] // NOTE: This must remain before the derives for `Serialize` and `Deserialize`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Event {
+    /// The Target Off-Block Time (TOBT) of a departure has been updated.
+    ///
+    /// The departure's earliest take-off time moves by the same amount as its TOBT, while its base time is left
+    /// unchanged so that its delay is still measured against the original plan.
+    #[serde(rename_all = "kebab-case")]
+    TobtUpdated {
+        /// The index of the departure.
+        flight_index: usize,
+        /// The new TOBT of the departure.
+        tobt: NaiveDateTime,
+    },
+    /// A departure has been issued a CTOT slot, replacing its previous slot if it had one.
+    #[serde(rename_all = "kebab-case")]
+    CtotIssued {
+        /// The index of the departure.
+        flight_index: usize,
+        /// The new CTOT slot of the departure.
+        ctot: Ctot,
+    },
+    /// The CTOT slot of a departure has been revoked.
+    #[serde(rename_all = "kebab-case")]
+    CtotRevoked {
+        /// The index of the departure.
+        flight_index: usize,
+    },
+    /// The de-icing requirement of a departure has changed.
+    ///
+    /// The departure's earliest take-off time moves by the change in the time it takes to taxi to the de-icing pad
+    /// and be de-iced.
+    #[serde(rename_all = "kebab-case")]
+    DeiceChanged {
+        /// The index of the departure.
+        flight_index: usize,
+        /// The new de-icing parameters of the departure, or [`None`] if it no longer needs to be de-iced.
+        deice: Option<Deice>,
+    },
+    /// A new aircraft has been added, after all existing aircraft in the instance.
+    #[serde(rename_all = "kebab-case")]
+    FlightAdded {
+        /// The new aircraft.
+        flight: Box<Flight>,
+        /// The separations from the new aircraft to every existing aircraft, when the new aircraft lands or takes off
+        /// first.
+        #[serde_as(as = "Vec<DurationSeconds>")]
+        separations_from: Vec<Duration>,
+        /// The separations from every existing aircraft to the new aircraft, when the new aircraft lands or takes off
+        /// second.
+        #[serde_as(as = "Vec<DurationSeconds>")]
+        separations_to: Vec<Duration>,
+        /// The separation between the new aircraft and itself.
+        #[serde_as(as = "DurationSeconds")]
+        self_separation: Duration,
+    },
+    /// An aircraft has been cancelled, and is removed from the instance along with its pin and reference schedule.
+    ///
+    /// The aircraft after it in the instance move down by one index.
+    #[serde(rename_all = "kebab-case")]
+    FlightCancelled {
+        /// The index of the aircraft.
+        flight_index: usize,
+    },
+}
+
+/// The error returned when an [`Event`] cannot be applied to an [`Instance`].
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Error)]
+pub enum EventError {
+    /// The event refers to an aircraft that does not exist in the instance.
+    #[error("aircraft {} does not exist in the instance", .flight_index)]
+    UnknownFlight {
+        /// The index of the aircraft.
+        flight_index: usize,
+    },
+    /// The event only applies to departures, but refers to an arrival.
+    #[error("aircraft {} is not a departure", .flight_index)]
+    NotDeparture {
+        /// The index of the aircraft.
+        flight_index: usize,
+    },
+    /// The separations of an added aircraft do not cover every existing aircraft in the instance.
+    #[error("expected {} separations for the added aircraft, but got {}", .expected, .actual)]
+    SeparationsLen {
+        /// The number of aircraft in the instance.
+        expected: usize,
+        /// The number of separations given.
+        actual: usize,
+    },
+    /// The pin of an aircraft no longer matches the aircraft after the event, such as a pinned de-icing slot of a
+    /// departure that no longer needs to be de-iced.
+    #[error("aircraft {} has an invalid pin after the event", .flight_index)]
+    InvalidPin {
+        /// The index of the aircraft.
+        flight_index: usize,
+    },
+}
+
+/// The result of applying one or more [`Event`]s to an [`Instance`].
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Update {
+    /// The updated instance.
+    pub instance: Instance,
+    /// The mapping from the aircraft indices of the original instance to those of the updated instance.
+    pub remap: Remap,
+}
+
+/// A mapping from the aircraft indices of an [`Instance`] to those of the instance produced by applying [`Event`]s to
+/// it.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Remap {
+    // NOTE: This is indexed by the old aircraft indices, with `None` for aircraft that were cancelled.
+    indices: Box<[Option<usize>]>,
+    len: usize,
+}
+
+impl Remap {
+    /// Creates a mapping for an instance with the given number of aircraft that leaves every aircraft in place.
+    pub fn identity(len: usize) -> Self {
+        Self {
+            indices: (0..len).map(Some).collect(),
+            len,
+        }
+    }
+
+    /// Returns the new index of an aircraft, or [`None`] if it was cancelled or never existed.
+    pub fn get(&self, flight_idx: usize) -> Option<usize> {
+        self.indices.get(flight_idx).copied().flatten()
+    }
+
+    /// Returns an iterator over the new indices of all aircraft that were added, in ascending order.
+    pub fn added(&self) -> impl Iterator<Item = usize> + '_ {
+        let mut kept = vec![false; self.len];
+        for &new_idx in self.indices.iter().flatten() {
+            kept[new_idx] = true;
+        }
+        (0..self.len).filter(move |&new_idx| !kept[new_idx])
+    }
+
+    /// Remaps a scheduled aircraft to the new aircraft indices, returning [`None`] if it was cancelled.
+    pub fn schedule(&self, sched: &Schedule) -> Option<Schedule> {
+        let flight_idx = self.get(sched.flight_index())?;
+        Some(with_flight_index(sched, flight_idx))
+    }
+
+    /// Remaps a runway sequence to the new aircraft indices, leaving out any aircraft that were cancelled.
+    ///
+    /// Aircraft that were added are not part of the sequence.
+    pub fn schedules(&self, schedules: &[Schedule]) -> Vec<Schedule> {
+        schedules
+            .iter()
+            .filter_map(|sched| self.schedule(sched))
+            .collect()
+    }
+
+    /// Combines this mapping with a mapping from the resulting instance to a later one.
+    pub fn then(&self, next: &Self) -> Self {
+        Self {
+            indices: self
+                .indices
+                .iter()
+                .map(|&idx| idx.and_then(|idx| next.get(idx)))
+                .collect(),
+            len: next.len,
+        }
+    }
+}
+
+pub(crate) fn apply(instance: &Instance, event: &Event) -> Result<Update, EventError> {
+    let mut instance = instance.clone();
+    let len = instance.flights.len();
+
+    let remap = match event {
+        Event::TobtUpdated { flight_index, tobt } => {
+            let dep = departure_mut(&mut instance, *flight_index)?;
+            dep.earliest_time += *tobt - dep.tobt;
+            dep.tobt = *tobt;
+            Remap::identity(len)
+        },
+        Event::CtotIssued { flight_index, ctot } => {
+            departure_mut(&mut instance, *flight_index)?.ctot = Some(ctot.clone());
+            Remap::identity(len)
+        },
+        Event::CtotRevoked { flight_index } => {
+            departure_mut(&mut instance, *flight_index)?.ctot = None;
+            Remap::identity(len)
+        },
+        Event::DeiceChanged {
+            flight_index,
+            deice,
+        } => {
+            let dep = departure_mut(&mut instance, *flight_index)?;
+            dep.earliest_time =
+                dep.earliest_time - deice_lead(dep.deice.as_ref()) + deice_lead(deice.as_ref());
+            dep.deice = deice.clone();
+            Remap::identity(len)
+        },
+        Event::FlightAdded {
+            flight,
+            separations_from,
+            separations_to,
+            self_separation,
+        } => {
+            for separations in [separations_from, separations_to] {
+                if separations.len() != len {
+                    return Err(EventError::SeparationsLen {
+                        expected: len,
+                        actual: separations.len(),
+                    });
+                }
+            }
+
+            let mut data = Vec::with_capacity((len + 1) * (len + 1));
+            for (from, &sep_to) in separations_to.iter().enumerate() {
+                data.extend((0..len).map(|to| instance.separations[(from, to)]));
+                data.push(sep_to);
+            }
+            data.extend(separations_from);
+            data.push(*self_separation);
+
+            // PANICS: The separation matrix is always built with exactly one more row and column than before.
+            instance.separations = Separations::new(data, len + 1).unwrap();
+            let mut flights = Vec::from(instance.flights);
+            flights.push(Flight::clone(flight));
+            instance.flights = flights.into();
+
+            Remap {
+                indices: (0..len).map(Some).collect(),
+                len: len + 1,
+            }
+        },
+        Event::FlightCancelled { flight_index } => {
+            if *flight_index >= len {
+                return Err(EventError::UnknownFlight {
+                    flight_index: *flight_index,
+                });
+            }
+
+            let kept = (0..len)
+                .filter(|&flight_idx| flight_idx != *flight_index)
+                .collect::<Vec<_>>();
+            let data = kept
+                .iter()
+                .flat_map(|&from| kept.iter().map(move |&to| (from, to)))
+                .map(|(from, to)| instance.separations[(from, to)])
+                .collect::<Vec<_>>();
+
+            // PANICS: The separation matrix is always built with exactly one less row and column than before.
+            instance.separations = Separations::new(data, len - 1).unwrap();
+            let mut flights = Vec::from(instance.flights);
+            flights.remove(*flight_index);
+            instance.flights = flights.into();
+
+            Remap {
+                indices: (0..len)
+                    .map(|flight_idx| match flight_idx.cmp(flight_index) {
+                        Ordering::Less => Some(flight_idx),
+                        Ordering::Equal => None,
+                        Ordering::Greater => Some(flight_idx - 1),
+                    })
+                    .collect(),
+                len: len - 1,
+            }
+        },
+    };
+
+    instance.pinned = instance
+        .pinned
+        .iter()
+        .filter_map(|pin| remap_pin(pin, &remap))
+        .collect();
+    instance.reference = remap.schedules(&instance.reference).into();
+
+    if let Some(&flight_index) = validate::invalid_pins(&instance).first() {
+        return Err(EventError::InvalidPin { flight_index });
+    }
+
+    Ok(Update { instance, remap })
+}
+
+fn departure_mut(
+    instance: &mut Instance,
+    flight_index: usize,
+) -> Result<&mut Departure, EventError> {
+    match instance.flights.get_mut(flight_index) {
+        Some(Flight::Dep(dep)) => Ok(dep),
+        Some(Flight::Arr(_)) => Err(EventError::NotDeparture { flight_index }),
+        None => Err(EventError::UnknownFlight { flight_index }),
+    }
+}
+
+// The time taken to taxi to the de-icing pad and be de-iced, which is part of a departure's earliest take-off time
+fn deice_lead(deice: Option<&Deice>) -> Duration {
+    deice
+        .map(|deice| deice.taxi_duration + deice.duration)
+        .unwrap_or_default()
+}
+
+fn remap_pin(pin: &Pin, remap: &Remap) -> Option<Pin> {
+    match pin {
+        Pin::Schedule(sched) => remap.schedule(sched).map(Pin::Schedule),
+        Pin::Deice(slot) => {
+            let mut slot = slot.clone();
+            slot.flight_index = remap.get(slot.flight_index)?;
+            Some(Pin::Deice(slot))
+        },
+    }
+}
+
+fn with_flight_index(sched: &Schedule, flight_index: usize) -> Schedule {
+    let mut sched = sched.clone();
+    match &mut sched {
+        Schedule::Arr(arr) => arr.flight_index = flight_index,
+        Schedule::Dep(dep) => dep.flight_index = flight_index,
+    }
+    sched
+}
@@ -84,6 +84,22 @@ impl Flight {
             Self::Dep(dep) => dep.release_time(),
         }
     }
+
+    /// Returns the indices of the runways the aircraft is allowed to use, or [`None`] if it can use any runway.
+    pub fn runways(&self) -> Option<&[usize]> {
+        match self {
+            Self::Arr(arr) => arr.runways.as_deref(),
+            Self::Dep(dep) => dep.runways.as_deref(),
+        }
+    }
+
+    /// Returns the identifying information and metadata of the aircraft, if any.
+    pub fn info(&self) -> Option<&FlightInfo> {
+        match self {
+            Self::Arr(arr) => arr.info.as_ref(),
+            Self::Dep(dep) => dep.info.as_ref(),
+        }
+    }
 }
 
 impl From<Arrival> for Flight {
@@ -108,6 +124,12 @@ pub struct Arrival {
     pub base_time: NaiveDateTime,
     /// The hard time window of the arrival.
     pub window: Option<TimeWindow>,
+    /// The indices of the runways the arrival is allowed to land on, or [`None`] if it can land on any runway.
+    #[serde(default)]
+    pub runways: Option<Vec<usize>>,
+    /// The identifying information and metadata of the arrival, if any.
+    #[serde(default)]
+    pub info: Option<FlightInfo>,
 }
 
 impl Arrival {
@@ -146,6 +168,13 @@ pub struct Departure {
     pub ctot: Option<Ctot>,
     /// The hard time window of the departure.
     pub window: Option<TimeWindow>,
+    /// The indices of the runways the departure is allowed to take off from, or [`None`] if it can take off from any
+    /// runway.
+    #[serde(default)]
+    pub runways: Option<Vec<usize>>,
+    /// The identifying information and metadata of the departure, if any.
+    #[serde(default)]
+    pub info: Option<FlightInfo>,
 }
 
 impl Departure {
@@ -162,6 +191,42 @@ impl Departure {
     }
 }
 
+/// Identifying information and metadata of an aircraft.
+///
+/// None of this information is used when solving an [`Instance`](crate::Instance), but it allows scheduled aircraft
+/// to be traced back to the real flights they represent even after an instance is reordered or sliced.
+/// It is kept when an instance is serialized and deserialized, and is written to a separate sheet when an instance is
+/// exported to Excel, although exported workbooks cannot be read back into an instance.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct FlightInfo {
+    /// A unique identifier of the aircraft within the dataset it originates from.
+    pub id: Option<String>,
+    /// The callsign of the flight.
+    pub callsign: Option<String>,
+    /// The registration of the aircraft.
+    pub registration: Option<String>,
+    /// The type or model of the aircraft.
+    pub aircraft_type: Option<String>,
+    /// The wake turbulence or size class of the aircraft.
+    pub weight_class: Option<String>,
+    /// The stand the aircraft arrives at or departs from.
+    pub stand: Option<String>,
+    /// The route the aircraft takes between its stand and the runway.
+    pub route: Option<String>,
+}
+
+impl FlightInfo {
+    /// Returns the most human-readable name of the aircraft available, preferring its callsign, then its registration,
+    /// and then its identifier.
+    pub fn name(&self) -> Option<&str> {
+        self.callsign
+            .as_deref()
+            .or(self.registration.as_deref())
+            .or(self.id.as_deref())
+    }
+}
+
 /// A departure's de-icing parameters and information.
 #[serde_as] // NOTE: This must remain before the derives for `Serialize` and `Deserialize`.
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
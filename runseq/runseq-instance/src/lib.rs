@@ -5,37 +5,134 @@
 
 use std::time::Duration;
 
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize};
 
 use serde_with::{serde_as, DurationSeconds};
 
+pub mod deice;
+use deice::DeicePad;
+
+pub mod event;
+use event::{Event, EventError, Remap, Update};
+
 pub mod flight;
-use flight::Flight;
+use flight::{Deice, Flight};
+
+pub mod pin;
+use pin::Pin;
+
+pub mod runway;
+use runway::{Runway, RunwayPair, RunwayRelation};
 
 pub mod schedule;
 use schedule::Schedule;
 
+pub mod solution;
+use solution::Solution;
+
 pub mod sep;
 use sep::{Separations, SeparationsAsSeconds, SeparationsMut};
 
 pub mod solve;
-use solve::Solve;
+use solve::{Solve, SolveError};
+
+pub mod validate;
+use validate::Diagnostic;
+
+pub mod verify;
 
 /// A runway sequencing problem instance.
+///
+/// Deserializing an instance fails with a [`Diagnostic`] if its parts are inconsistent with each other in a way that
+/// the constructors would reject.
 #[serde_as] // NOTE: This must remain before the derives for `Serialize` and `Deserialize`.
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
-#[serde(rename_all = "kebab-case")]
+#[serde(rename_all = "kebab-case", try_from = "UncheckedInstance")]
 pub struct Instance {
     flights: Box<[Flight]>,
     #[serde_as(as = "SeparationsAsSeconds")]
     separations: Separations,
+    runways: Box<[Runway]>,
+    runway_pairs: Box<[RunwayPair]>,
+    deice_pads: Box<[DeicePad]>,
+    pinned: Box<[Pin]>,
+    reference: Box<[Schedule]>,
+    /// The maximum number of positions any aircraft may be moved away from its position in the
+    /// [first-come-first-served order](Instance::fcfs_positions), if any.
+    ///
+    /// This is commonly known as Constrained Position Shifting (CPS), and ensures that no aircraft is delayed or moved
+    /// forward by too many positions for the sake of a cheaper sequence.
+    pub max_position_shift: Option<usize>,
     /// The maximum runway hold duration allowed for all aircraft in the instance.
     #[serde_as(as = "DurationSeconds")]
     pub max_runway_hold_duration: Duration,
 }
 
+// NOTE: This must have the same fields as `Instance`, which is only constructed from it once the fields have been
+//       checked against each other.
+#[serde_as]
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct UncheckedInstance {
+    flights: Box<[Flight]>,
+    #[serde_as(as = "SeparationsAsSeconds")]
+    separations: Separations,
+    #[serde(
+        default = "default_runways",
+        deserialize_with = "deserialize_non_empty"
+    )]
+    runways: Box<[Runway]>,
+    #[serde(default)]
+    runway_pairs: Box<[RunwayPair]>,
+    #[serde(
+        default = "default_deice_pads",
+        deserialize_with = "deserialize_non_empty"
+    )]
+    deice_pads: Box<[DeicePad]>,
+    #[serde(default)]
+    pinned: Box<[Pin]>,
+    #[serde(default)]
+    reference: Box<[Schedule]>,
+    #[serde(default)]
+    max_position_shift: Option<usize>,
+    #[serde_as(as = "DurationSeconds")]
+    max_runway_hold_duration: Duration,
+}
+
+impl TryFrom<UncheckedInstance> for Instance {
+    type Error = Diagnostic;
+
+    fn try_from(unchecked: UncheckedInstance) -> Result<Self, Self::Error> {
+        let instance = Self {
+            flights: unchecked.flights,
+            separations: unchecked.separations,
+            runways: unchecked.runways,
+            runway_pairs: unchecked.runway_pairs,
+            deice_pads: unchecked.deice_pads,
+            pinned: unchecked.pinned,
+            reference: unchecked.reference,
+            max_position_shift: unchecked.max_position_shift,
+            max_runway_hold_duration: unchecked.max_runway_hold_duration,
+        };
+
+        // NOTE: Solvers index runways by the pairs of runways, and aircraft by their pins and reference schedules,
+        //       without checking them first.
+        if let Some(&pair_index) = validate::invalid_runway_pairs(&instance).first() {
+            return Err(Diagnostic::InvalidRunwayPair { pair_index });
+        }
+        if let Some(&flight_index) = validate::invalid_pins(&instance).first() {
+            return Err(Diagnostic::InvalidPin { flight_index });
+        }
+        if let Some(&position) = validate::invalid_references(&instance).first() {
+            return Err(Diagnostic::InvalidReference { position });
+        }
+
+        Ok(instance)
+    }
+}
+
 impl Instance {
-    /// Creates a new instance with the specified parameters, returning [`None`] if the number of
+    /// Creates a new instance with a single runway and the specified parameters, returning [`None`] if the number of
     /// aircraft does not match the number of rows or columns in the separation matrix.
     pub fn new<F>(
         flights: F,
@@ -44,13 +141,92 @@ impl Instance {
     ) -> Option<Self>
     where
         F: Into<Box<[Flight]>>,
+    {
+        Self::with_runways(
+            flights,
+            separations,
+            default_runways(),
+            Vec::new(),
+            max_runway_hold_duration,
+        )
+    }
+
+    /// Creates a new instance with multiple runways and the specified parameters, returning [`None`] if the number of
+    /// aircraft does not match the number of rows or columns in the separation matrix, if there are no runways, or if
+    /// a pair of runways refers to a runway that does not exist.
+    pub fn with_runways<F, R, P>(
+        flights: F,
+        separations: Separations,
+        runways: R,
+        runway_pairs: P,
+        max_runway_hold_duration: Duration,
+    ) -> Option<Self>
+    where
+        F: Into<Box<[Flight]>>,
+        R: Into<Box<[Runway]>>,
+        P: Into<Box<[RunwayPair]>>,
     {
         let flights = flights.into();
-        (flights.len() == separations.len()).then_some(Self {
+        let runways = runways.into();
+        let runway_pairs = runway_pairs.into();
+
+        let instance = Self {
             flights,
             separations,
+            runways,
+            runway_pairs,
+            deice_pads: default_deice_pads(),
+            pinned: Box::new([]),
+            reference: Box::new([]),
+            max_position_shift: None,
             max_runway_hold_duration,
-        })
+        };
+        let valid = instance.flights.len() == instance.separations.len()
+            && !instance.runways.is_empty()
+            && validate::invalid_runway_pairs(&instance).is_empty();
+        valid.then_some(instance)
+    }
+
+    /// Replaces the de-icing pads of the instance, returning [`None`] if there are no de-icing pads.
+    ///
+    /// Instances have a single de-icing pad by default.
+    pub fn with_deice_pads<D>(mut self, deice_pads: D) -> Option<Self>
+    where
+        D: Into<Box<[DeicePad]>>,
+    {
+        self.deice_pads = deice_pads.into();
+        (!self.deice_pads.is_empty()).then_some(self)
+    }
+
+    /// Replaces the pinned aircraft of the instance, returning [`None`] if a pin refers to an aircraft that does not
+    /// exist, if an aircraft is pinned more than once, or if a pin does not match the kind of aircraft, its runways, or
+    /// its de-icing requirements.
+    ///
+    /// Instances have no pinned aircraft by default.
+    pub fn with_pinned<P>(mut self, pinned: P) -> Option<Self>
+    where
+        P: Into<Box<[Pin]>>,
+    {
+        self.pinned = pinned.into();
+        validate::invalid_pins(&self).is_empty().then_some(self)
+    }
+
+    /// Replaces the reference sequence of the instance, returning [`None`] if a scheduled aircraft does not exist or
+    /// is scheduled more than once.
+    ///
+    /// The reference sequence is a previously published runway sequence that solvers can be asked to stay close to,
+    /// so that a new sequence does not reshuffle aircraft for only a small improvement.
+    /// It does not need to contain every aircraft in the instance.
+    ///
+    /// Instances have no reference sequence by default.
+    pub fn with_reference<R>(mut self, reference: R) -> Option<Self>
+    where
+        R: Into<Box<[Schedule]>>,
+    {
+        self.reference = reference.into();
+        validate::invalid_references(&self)
+            .is_empty()
+            .then_some(self)
     }
 
     /// Extracts a slice of all aircraft in the instance.
@@ -85,6 +261,156 @@ impl Instance {
         }
     }
 
+    /// Extracts a slice of all runways in the instance.
+    pub fn runways(&self) -> &[Runway] {
+        &self.runways
+    }
+
+    /// Extracts a mutable slice of all runways in the instance.
+    pub fn runways_mut(&mut self) -> &mut [Runway] {
+        &mut self.runways
+    }
+
+    /// Extracts a slice of all explicitly specified relations between pairs of runways in the instance.
+    ///
+    /// See [`Instance::runway_relation`] for the relation between any two runways.
+    pub fn runway_pairs(&self) -> &[RunwayPair] {
+        &self.runway_pairs
+    }
+
+    /// Returns the relation between two runways.
+    ///
+    /// A runway is always [`RunwayRelation::Dependent`] on itself, as are any pairs of runways whose relation is not
+    /// explicitly specified.
+    pub fn runway_relation(&self, runway: usize, other: usize) -> RunwayRelation {
+        if runway == other {
+            return RunwayRelation::Dependent;
+        }
+        self.runway_pairs
+            .iter()
+            .find(|pair| pair.is_between(runway, other))
+            .map(|pair| pair.relation)
+            .unwrap_or_default()
+    }
+
+    /// Returns an iterator over the indices of all runways that an aircraft is allowed to use.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if there is no aircraft with the given index in the instance.
+    pub fn allowed_runways(&self, flight_idx: usize) -> impl Iterator<Item = usize> + '_ {
+        let allowed = self.flights[flight_idx].runways();
+        (0..self.runways.len()).filter(move |runway| match allowed {
+            None => true,
+            Some(allowed) => allowed.contains(runway),
+        })
+    }
+
+    /// Extracts a slice of all de-icing pads in the instance.
+    pub fn deice_pads(&self) -> &[DeicePad] {
+        &self.deice_pads
+    }
+
+    /// Extracts a mutable slice of all de-icing pads in the instance.
+    pub fn deice_pads_mut(&mut self) -> &mut [DeicePad] {
+        &mut self.deice_pads
+    }
+
+    /// Extracts a slice of all pinned aircraft in the instance.
+    pub fn pinned(&self) -> &[Pin] {
+        &self.pinned
+    }
+
+    /// Returns the pin of an aircraft, or [`None`] if the aircraft is not pinned.
+    pub fn pin(&self, flight_idx: usize) -> Option<&Pin> {
+        self.pinned
+            .iter()
+            .find(|pin| pin.flight_index() == flight_idx)
+    }
+
+    /// Extracts the reference sequence of the instance, in the order the aircraft are sequenced in.
+    ///
+    /// The reference sequence is empty if the instance has none.
+    pub fn reference(&self) -> &[Schedule] {
+        &self.reference
+    }
+
+    /// Returns the position of an aircraft in the reference sequence, or [`None`] if it is not part of the reference
+    /// sequence.
+    pub fn reference_position(&self, flight_idx: usize) -> Option<usize> {
+        self.reference
+            .iter()
+            .position(|sched| sched.flight_index() == flight_idx)
+    }
+
+    /// Returns the position of every aircraft in the reference sequence, indexed by aircraft index, or [`None`] for
+    /// aircraft that are not part of the reference sequence.
+    pub fn reference_positions(&self) -> Vec<Option<usize>> {
+        let mut positions = vec![None; self.flights.len()];
+        for (position, sched) in self.reference.iter().enumerate() {
+            positions[sched.flight_index()] = Some(position);
+        }
+        positions
+    }
+
+    /// Returns the position of every aircraft in the first-come-first-served order, indexed by aircraft index.
+    ///
+    /// Aircraft with pinned schedules come first in the order of their landing or take-off times, since they are always
+    /// sequenced before every other aircraft.
+    /// Every other aircraft follows in the order of its release time, with ties broken by the order aircraft appear in
+    /// the instance.
+    pub fn fcfs_positions(&self) -> Vec<usize> {
+        let mut order = (0..self.flights.len()).collect::<Vec<_>>();
+        order.sort_by_key(
+            |&flight_idx| match self.pin(flight_idx).and_then(Pin::as_schedule) {
+                Some(sched) => (false, sched.flight_time()),
+                None => (true, self.flights[flight_idx].release_time()),
+            },
+        );
+
+        let mut positions = vec![0; self.flights.len()];
+        for (position, flight_idx) in order.into_iter().enumerate() {
+            positions[flight_idx] = position;
+        }
+        positions
+    }
+
+    /// Returns the amount of time taken to de-ice a departure at the given de-icing pad.
+    ///
+    /// This is the de-icing duration of the pad if it has one, or the de-icing duration of the departure otherwise.
+    pub fn deice_duration(&self, deice: &Deice, pad: usize) -> Duration {
+        self.deice_pads
+            .get(pad)
+            .and_then(|pad| pad.duration)
+            .unwrap_or(deice.duration)
+    }
+
+    /// Returns the minimum separation between two aircraft `from` and `to` on the given runways, where `from` lands or
+    /// takes off before `to`, or [`None`] if the two aircraft do not need to be separated at all.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if either of the aircraft indices are out of bounds.
+    pub fn separation(
+        &self,
+        from: usize,
+        from_runway: usize,
+        to: usize,
+        to_runway: usize,
+    ) -> Option<Duration> {
+        let separated = match self.runway_relation(from_runway, to_runway) {
+            RunwayRelation::Independent => false,
+            RunwayRelation::Dependent => true,
+            RunwayRelation::Segregated => {
+                matches!(
+                    (&self.flights[from], &self.flights[to]),
+                    (Flight::Arr(_), Flight::Arr(_)) | (Flight::Dep(_), Flight::Dep(_))
+                )
+            },
+        };
+        separated.then(|| self.separations[(from, to)])
+    }
+
     /// Extracts the separation matrix from the instance, consuming it in the process.
     pub fn into_separations(self) -> Separations {
         self.separations
@@ -95,8 +421,52 @@ impl Instance {
         (self.flights, self.separations)
     }
 
+    /// Checks the instance for malformed or inconsistent data, returning a list of problems found.
+    ///
+    /// An empty list means that no problems were found, although this does not guarantee that the instance has a
+    /// feasible solution.
+    /// See [`Diagnostic`] for the problems that are checked.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        validate::validate(self)
+    }
+
+    /// Applies an [`Event`] to the instance, producing a new instance along with a mapping from the aircraft indices of
+    /// this instance to those of the new one.
+    ///
+    /// Pins and the reference sequence are carried over to the new instance, except for those of cancelled aircraft.
+    ///
+    /// Returns an [`EventError`] if the event refers to an aircraft that does not exist or is of the wrong kind, if an
+    /// added aircraft does not come with a separation for every existing aircraft, or if the event invalidates a pin.
+    pub fn apply(&self, event: &Event) -> Result<Update, EventError> {
+        event::apply(self, event)
+    }
+
+    /// Applies several [`Event`]s to the instance one after the other, producing a new instance along with a mapping
+    /// from the aircraft indices of this instance to those of the new one.
+    ///
+    /// The aircraft indices in every event refer to the instance produced by the events before it.
+    ///
+    /// Returns an [`EventError`] for the first event that cannot be applied.
+    pub fn apply_all<'a, E>(&self, events: E) -> Result<Update, EventError>
+    where
+        E: IntoIterator<Item = &'a Event>,
+    {
+        let mut update = Update {
+            instance: self.clone(),
+            remap: Remap::identity(self.flights.len()),
+        };
+        for event in events {
+            let next = event::apply(&update.instance, event)?;
+            update = Update {
+                instance: next.instance,
+                remap: update.remap.then(&next.remap),
+            };
+        }
+        Ok(update)
+    }
+
     /// Solves the instance using a default value of a [`Solve`]r.
-    pub fn solve<S>(&self) -> Option<Vec<Schedule>>
+    pub fn solve<S>(&self) -> Result<Solution, SolveError>
     where
         S: Solve + Default,
     {
@@ -105,10 +475,141 @@ impl Instance {
     }
 
     /// Solves the instance using the given [`Solve`]r.
-    pub fn solve_with<S>(&self, solver: &S) -> Option<Vec<Schedule>>
+    pub fn solve_with<S>(&self, solver: &S) -> Result<Solution, SolveError>
     where
         S: Solve,
     {
         solver.solve(self)
     }
 }
+
+fn default_runways() -> Box<[Runway]> {
+    Box::new([Runway::default()])
+}
+
+fn default_deice_pads() -> Box<[DeicePad]> {
+    Box::new([DeicePad::default()])
+}
+
+// NOTE: Solvers rely on every instance having at least one runway and de-icing pad, which the constructors check but
+//       deserialization would otherwise skip.
+fn deserialize_non_empty<'de, D, T>(deserializer: D) -> Result<Box<[T]>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    let items = Box::<[T]>::deserialize(deserializer)?;
+    if items.is_empty() {
+        return Err(de::Error::invalid_length(0, &"at least one element"));
+    }
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    use toml::{Table, Value};
+
+    use crate::{
+        flight::{Arrival, Flight},
+        pin::Pin,
+        runway::Runway,
+        schedule::{ArrivalSchedule, Schedule},
+        sep::Separations,
+        Instance,
+    };
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    fn start() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, 1)
+            .and_then(|date| date.and_hms_opt(10, 0, 0))
+            .unwrap()
+    }
+
+    fn instance() -> Instance {
+        let flights = (0..3)
+            .map(|idx| {
+                let time = start() + MINUTE * idx;
+                Flight::Arr(Arrival {
+                    earliest_time: time,
+                    base_time: time,
+                    window: None,
+                    runways: None,
+                    info: None,
+                })
+            })
+            .collect::<Vec<_>>();
+        let separations = Separations::new(vec![MINUTE; 9], 3).unwrap();
+
+        Instance::with_runways(
+            flights,
+            separations,
+            vec![Runway::default(); 2],
+            Vec::new(),
+            MINUTE * 10,
+        )
+        .unwrap()
+    }
+
+    // Deserializes an instance after replacing one of its serialized fields
+    fn deserialize_with(key: &str, value: Value) -> Result<Instance, toml::de::Error> {
+        let mut table = Table::try_from(instance()).unwrap();
+        table.insert(key.to_owned(), value);
+        toml::from_str(&toml::to_string(&table).unwrap())
+    }
+
+    #[test]
+    fn deserialization_rejects_runway_pairs_with_unknown_runways() {
+        let pair = |second: i64| {
+            let mut pair = Table::new();
+            pair.insert("first".to_owned(), Value::Integer(0));
+            pair.insert("second".to_owned(), Value::Integer(second));
+            pair.insert("relation".to_owned(), Value::from("independent"));
+            Value::Array(vec![Value::Table(pair)])
+        };
+
+        assert!(deserialize_with("runway-pairs", pair(1)).is_ok());
+        assert!(deserialize_with("runway-pairs", pair(2)).is_err());
+    }
+
+    #[test]
+    fn deserialization_rejects_invalid_pins() {
+        let pin = |flight_index, runway| {
+            let pin = Pin::Schedule(Schedule::Arr(ArrivalSchedule {
+                flight_index,
+                landing: start(),
+                runway,
+            }));
+            Value::try_from(vec![pin]).unwrap()
+        };
+
+        assert!(deserialize_with("pinned", pin(0, 1)).is_ok());
+        assert!(deserialize_with("pinned", pin(0, 2)).is_err());
+        assert!(deserialize_with("pinned", pin(7, 0)).is_err());
+    }
+
+    #[test]
+    fn deserialization_rejects_invalid_reference_schedules() {
+        let reference = |flight_indices: &[usize]| {
+            let reference = flight_indices
+                .iter()
+                .map(|&flight_index| {
+                    Schedule::Arr(ArrivalSchedule {
+                        flight_index,
+                        landing: start(),
+                        runway: 0,
+                    })
+                })
+                .collect::<Vec<_>>();
+            Value::try_from(reference).unwrap()
+        };
+
+        assert!(deserialize_with("reference", reference(&[2, 0])).is_ok());
+        assert!(deserialize_with("reference", reference(&[2, 2])).is_err());
+        assert!(deserialize_with("reference", reference(&[7])).is_err());
+    }
+}
@@ -0,0 +1,73 @@
+//! Aircraft whose schedules have already been decided.
+//!
+//! During the day, some aircraft have already pushed back, de-iced, or taken off, so their times can no longer change
+//! when the remaining aircraft are sequenced again.
+//! Such aircraft are pinned in an [`Instance`](crate::Instance) using
+//! [`Instance::with_pinned`](crate::Instance::with_pinned).
+
+use chrono::NaiveDateTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::schedule::Schedule;
+
+/// A part of an aircraft's schedule that has already been decided and can no longer change.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Pin {
+    /// The entire schedule of the aircraft is fixed, such as for an aircraft that has already landed or taken off.
+    ///
+    /// Solvers sequence all aircraft with fixed schedules before any other aircraft, in the order of their landing or
+    /// take-off times.
+    Schedule(Schedule),
+    /// Only the de-icing slot of a departure is fixed, such as for a departure that is de-icing or has already been
+    /// de-iced.
+    ///
+    /// Solvers still decide the take-off time and runway of the departure, but no other departure can be de-iced at
+    /// the same de-icing pad until the slot ends.
+    Deice(DeiceSlot),
+}
+
+impl Pin {
+    /// Returns the index of the pinned aircraft in its [`Instance`](crate::Instance).
+    pub fn flight_index(&self) -> usize {
+        match self {
+            Self::Schedule(sched) => sched.flight_index(),
+            Self::Deice(slot) => slot.flight_index,
+        }
+    }
+
+    /// Attempts to extract the pinned schedule, returning [`None`] if only a de-icing slot is pinned.
+    pub fn as_schedule(&self) -> Option<&Schedule> {
+        match self {
+            Self::Schedule(sched) => Some(sched),
+            Self::Deice(_) => None,
+        }
+    }
+
+    /// Returns the pinned de-icing time and de-icing pad of the departure, if any.
+    ///
+    /// This includes the de-icing time of departures whose entire schedule is pinned.
+    pub fn deice(&self) -> Option<(NaiveDateTime, usize)> {
+        match self {
+            Self::Schedule(sched) => {
+                let sched = sched.as_departure()?;
+                Some((sched.deice?, sched.deice_pad))
+            },
+            Self::Deice(slot) => Some((slot.deice, slot.deice_pad)),
+        }
+    }
+}
+
+/// A fixed de-icing slot of a departure.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct DeiceSlot {
+    /// The departure's index in its [`Instance`](crate::Instance).
+    pub flight_index: usize,
+    /// The time at which the departure starts de-icing.
+    pub deice: NaiveDateTime,
+    /// The index of the de-icing pad the departure is de-iced at.
+    #[serde(default)]
+    pub deice_pad: usize,
+}
@@ -0,0 +1,52 @@
+//! Runways and the relations between them.
+//!
+//! An [`Instance`](crate::Instance) always has at least one runway.
+//! Separations between aircraft on the same runway are always given by the instance's [separation matrix](crate::sep),
+//! while separations between aircraft on different runways depend on the [`RunwayRelation`] between those runways.
+
+use serde::{Deserialize, Serialize};
+
+/// A runway that aircraft can land on or take off from.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Runway {
+    /// The name or designator of the runway (such as `27R`), if any.
+    pub name: Option<String>,
+}
+
+/// How operations on two different runways affect each other.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RunwayRelation {
+    /// Aircraft on the two runways do not need to be separated from each other.
+    Independent,
+    /// Aircraft on the two runways are separated as if they were on the same runway.
+    #[default]
+    Dependent,
+    /// Aircraft of the same kind (arrivals or departures) on the two runways are separated as if they were on the same
+    /// runway, while an arrival on one runway and a departure on the other do not need to be separated.
+    Segregated,
+}
+
+/// The relation between a pair of runways.
+///
+/// Relations are symmetric - the order of the two runways does not matter.
+/// Pairs of runways without an explicit relation are [`RunwayRelation::Dependent`].
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RunwayPair {
+    /// The index of the first runway in the pair.
+    pub first: usize,
+    /// The index of the second runway in the pair.
+    pub second: usize,
+    /// The relation between the two runways.
+    pub relation: RunwayRelation,
+}
+
+impl RunwayPair {
+    /// Checks if the pair consists of the two given runways, in any order.
+    pub fn is_between(&self, runway: usize, other: usize) -> bool {
+        (self.first == runway && self.second == other)
+            || (self.first == other && self.second == runway)
+    }
+}
@@ -57,6 +57,14 @@ impl Schedule {
         }
     }
 
+    /// Returns the index of the runway the aircraft is scheduled to land on or take off from.
+    pub fn runway(&self) -> usize {
+        match self {
+            Self::Arr(arr) => arr.runway,
+            Self::Dep(dep) => dep.runway,
+        }
+    }
+
     /// Returns the scheduled landing or take-off time of the aircraft.
     pub fn flight_time(&self) -> NaiveDateTime {
         match self {
@@ -86,6 +94,9 @@ pub struct ArrivalSchedule {
     pub flight_index: usize,
     /// The arrival's scheduled landing time.
     pub landing: NaiveDateTime,
+    /// The index of the runway the arrival is scheduled to land on.
+    #[serde(default)]
+    pub runway: usize,
 }
 
 /// A scheduled departure.
@@ -96,6 +107,14 @@ pub struct DepartureSchedule {
     pub flight_index: usize,
     /// The departure's scheduled de-icing time, if any.
     pub deice: Option<NaiveDateTime>,
+    /// The index of the de-icing pad the departure is scheduled to de-ice at.
+    ///
+    /// This is only meaningful if the departure has a de-icing time.
+    #[serde(default)]
+    pub deice_pad: usize,
     /// The departure's scheduled take-off time.
     pub takeoff: NaiveDateTime,
+    /// The index of the runway the departure is scheduled to take off from.
+    #[serde(default)]
+    pub runway: usize,
 }
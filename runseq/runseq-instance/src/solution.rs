@@ -0,0 +1,190 @@
+//! Solutions to problem instances and metrics derived from them.
+
+use std::{collections::BTreeMap, time::Duration};
+
+use chrono::NaiveDateTime;
+
+use serde::{Deserialize, Serialize};
+
+use serde_with::{serde_as, DurationSeconds, DurationSecondsWithFrac};
+
+use crate::{
+    schedule::Schedule,
+    verify::{verify, Violation},
+    Instance,
+};
+
+/// A runway sequence produced by solving an [`Instance`], along with information about how it was produced.
+#[serde_as] // NOTE: This must remain before the derives for `Serialize` and `Deserialize`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Solution {
+    /// The scheduled landing or take-off times and de-icing times of all aircraft, in the order they are sequenced in.
+    pub schedules: Vec<Schedule>,
+    /// Metrics derived from the schedules, if any.
+    ///
+    /// These may be missing for solutions produced by external solvers.
+    #[serde(default)]
+    pub metrics: Option<Metrics>,
+    /// The constraints violated by the schedules as reported by [`verify`], if they have been checked.
+    ///
+    /// Solvers whose objectives penalise violating some constraints rather than forbidding them outright can produce
+    /// sequences that violate those constraints, which are reported here.
+    /// These may be missing for solutions produced by external solvers.
+    #[serde(default)]
+    pub violations: Option<Vec<Violation>>,
+    /// The solver that produced the solution, if known.
+    #[serde(default)]
+    pub solver: Option<SolverInfo>,
+    /// The wall-clock time taken to produce the solution, if known.
+    #[serde_as(as = "Option<DurationSecondsWithFrac<f64>>")]
+    #[serde(default)]
+    pub wall_time: Option<Duration>,
+    /// The number of nodes explored by the solver to produce the solution, if it explores a search tree.
+    #[serde(default)]
+    pub nodes: Option<u64>,
+    /// Whether the solution is proven to be optimal by the solver that produced it.
+    #[serde(default)]
+    pub optimal: bool,
+}
+
+impl Solution {
+    /// Creates a new solution from a runway sequence, calculating its [`Metrics`] and checking it for violated
+    /// constraints.
+    ///
+    /// The solution has no solver information, wall-clock time, or node count, and is not marked as optimal.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if any of the schedules refer to aircraft that are not in the instance.
+    pub fn new(schedules: Vec<Schedule>, instance: &Instance) -> Self {
+        let metrics = Metrics::new(&schedules, instance);
+        let violations = verify(&schedules, instance);
+        Self {
+            schedules,
+            metrics: Some(metrics),
+            violations: Some(violations),
+            solver: None,
+            wall_time: None,
+            nodes: None,
+            optimal: false,
+        }
+    }
+
+    /// Calculates the [`Metrics`] of the solution if they are missing.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if any of the schedules refer to aircraft that are not in the instance.
+    pub fn compute_metrics(&mut self, instance: &Instance) -> &Metrics {
+        self.metrics
+            .get_or_insert_with(|| Metrics::new(&self.schedules, instance))
+    }
+
+    /// Checks the schedules of the solution for violated constraints if they have not been checked yet.
+    pub fn compute_violations(&mut self, instance: &Instance) -> &[Violation] {
+        self.violations
+            .get_or_insert_with(|| verify(&self.schedules, instance))
+    }
+}
+
+/// The name and settings of the solver that produced a [`Solution`].
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SolverInfo {
+    /// The name of the solver.
+    pub name: String,
+    /// The settings the solver was configured with, by name.
+    #[serde(default)]
+    pub settings: BTreeMap<String, String>,
+}
+
+/// Metrics derived from a runway sequence.
+#[serde_as] // NOTE: This must remain before the derives for `Serialize` and `Deserialize`.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Metrics {
+    /// The time between the first and last landing or take-off.
+    #[serde_as(as = "DurationSeconds")]
+    pub makespan: Duration,
+    /// The sum of all aircraft's delays, where an aircraft's delay is the time between its base time and its scheduled
+    /// landing or take-off time.
+    #[serde_as(as = "DurationSeconds")]
+    pub total_delay: Duration,
+    /// The number of departures that take off outside their Calculated Take-Off Time (CTOT) slots.
+    pub ctot_violations: usize,
+    /// The sum of all de-iced departures' runway hold durations.
+    #[serde_as(as = "DurationSeconds")]
+    pub runway_hold: Duration,
+    /// The time when the first de-icing operation starts, if any departures are de-iced.
+    pub deice_start: Option<NaiveDateTime>,
+    /// The time when the last de-icing operation ends, if any departures are de-iced.
+    pub deice_end: Option<NaiveDateTime>,
+}
+
+impl Metrics {
+    /// Calculates the metrics of a runway sequence produced after solving an [`Instance`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if any of the schedules refer to aircraft that are not in the instance.
+    pub fn new(schedules: &[Schedule], instance: &Instance) -> Self {
+        let start = schedules.iter().map(Schedule::flight_time).min();
+        let end = schedules.iter().map(Schedule::flight_time).max();
+        let makespan = match start.zip(end) {
+            None => Duration::ZERO,
+            Some((start, end)) => (end - start).to_std().unwrap_or_default(),
+        };
+
+        let total_delay = schedules
+            .iter()
+            .map(|sched| {
+                let base_time = instance.flights()[sched.flight_index()].base_time();
+                (sched.flight_time() - base_time)
+                    .to_std()
+                    .unwrap_or_default()
+            })
+            .sum();
+
+        let mut ctot_violations = 0;
+        let mut runway_hold = Duration::ZERO;
+        let mut deice_start = None::<NaiveDateTime>;
+        let mut deice_end = None::<NaiveDateTime>;
+
+        let departures = schedules.iter().filter_map(|sched| {
+            let sched = sched.as_departure()?;
+            let dep = instance.flights()[sched.flight_index].as_departure()?;
+            Some((sched, dep))
+        });
+        for (sched, dep) in departures {
+            if let Some(ctot) = &dep.ctot {
+                if !ctot.as_range().contains(&sched.takeoff) {
+                    ctot_violations += 1;
+                }
+            }
+
+            let Some((deice, params)) = sched.deice.zip(dep.deice.as_ref()) else {
+                continue;
+            };
+
+            let end = deice + instance.deice_duration(params, sched.deice_pad);
+
+            // The runway hold is the time left over after de-icing, taxiing out, and lining up
+            runway_hold += (sched.takeoff - dep.lineup_duration - dep.taxi_duration - end)
+                .to_std()
+                .unwrap_or_default();
+
+            deice_start = Some(deice_start.map_or(deice, |start| start.min(deice)));
+            deice_end = Some(deice_end.map_or(end, |other| other.max(end)));
+        }
+
+        Self {
+            makespan,
+            total_delay,
+            ctot_violations,
+            runway_hold,
+            deice_start,
+            deice_end,
+        }
+    }
+}
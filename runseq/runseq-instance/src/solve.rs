@@ -1,9 +1,160 @@
 //! The [`Solve`] trait for representing runway sequencing solvers.
 
-use crate::{schedule::Schedule, Instance};
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Range,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use thiserror::Error;
+
+use crate::{solution::Solution, verify::Violation, Instance};
 
 /// A solver capable of producing solutions for a runway sequencing and de-icing [`Instance`].
 pub trait Solve {
-    /// Solves an [`Instance`] to produce a sequence of landing or take-off times and de-icing times.
-    fn solve(&self, instance: &Instance) -> Option<Vec<Schedule>>;
+    /// Solves an [`Instance`] to produce a [`Solution`] containing a sequence of landing or take-off times and de-icing
+    /// times.
+    ///
+    /// Returns a [`SolveError`] explaining why no solution could be produced if the solver fails.
+    fn solve(&self, instance: &Instance) -> Result<Solution, SolveError>;
+}
+
+/// The error returned when a [`Solve`]r fails to produce a [`Solution`].
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Error)]
+pub enum SolveError {
+    /// No feasible sequence could be found for a window of positions in the sequence.
+    ///
+    /// Solvers without a rolling horizon report the entire sequence as a single window.
+    #[error("no feasible sequence for positions {:?} of the sequence{}", .window, InfeasibleFlights(.flights))]
+    Infeasible {
+        /// The positions in the sequence that could not be filled.
+        window: Range<usize>,
+        /// The aircraft that could not be feasibly scheduled, and the constraint that prevented each of them from
+        /// being scheduled.
+        flights: Vec<InfeasibleFlight>,
+    },
+    /// The solver reached its time limit or node limit, or was cancelled, before it found a sequence for a window of
+    /// positions in the sequence.
+    #[error("stopped after exploring {} nodes before finding a sequence for positions {:?} of the sequence", .nodes, .window)]
+    LimitReached {
+        /// The positions in the sequence that could not be filled in time.
+        window: Range<usize>,
+        /// The number of nodes explored before the solver stopped.
+        nodes: u64,
+    },
+    /// The sequence given to the solver as a starting point violates the constraints of the instance.
+    #[error("the initial sequence is infeasible{}", Violations(.violations))]
+    InfeasibleWarmStart {
+        /// The constraints violated by the initial sequence.
+        violations: Vec<Violation>,
+    },
+}
+
+/// An aircraft that could not be feasibly scheduled.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct InfeasibleFlight {
+    /// The index of the aircraft.
+    pub flight_index: usize,
+    /// The constraint that prevented the aircraft from being scheduled.
+    pub constraint: Constraint,
+}
+
+/// A constraint that can prevent an aircraft from being scheduled.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Constraint {
+    /// The aircraft cannot land or take off within its hard time window.
+    Window,
+    /// The departure cannot take off before its Holdover Time (HOT) expires.
+    Hot,
+    /// The departure cannot take off without exceeding the maximum runway hold duration.
+    RunwayHold,
+    /// The departure's de-icing slot is too late for it to take off within its hard time window.
+    DeiceSlot,
+    /// The aircraft cannot be sequenced without it or another aircraft moving further from its first-come-first-served
+    /// position than allowed.
+    PositionShift,
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Window => write!(f, "time window"),
+            Self::Hot => write!(f, "holdover time"),
+            Self::RunwayHold => write!(f, "runway hold"),
+            Self::DeiceSlot => write!(f, "de-icing slot"),
+            Self::PositionShift => write!(f, "position shift"),
+        }
+    }
+}
+
+/// A token used to cancel a running [`Solve`]r from another thread.
+///
+/// Clones of a token share the same cancellation state, so cancelling one clone cancels all of them.
+/// Two tokens are equal if and only if they are clones of each other.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    /// Creates a new token that is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the token, along with all of its clones.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Checks if the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+impl PartialEq for CancelToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.cancelled, &other.cancelled)
+    }
+}
+
+impl Eq for CancelToken {}
+
+impl Hash for CancelToken {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.cancelled).hash(state);
+    }
+}
+
+struct InfeasibleFlights<'a>(&'a [InfeasibleFlight]);
+
+impl fmt::Display for InfeasibleFlights<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, flight) in self.0.iter().enumerate() {
+            let sep = if idx == 0 { ": " } else { ", " };
+            write!(
+                f,
+                "{}aircraft {} ({})",
+                sep, flight.flight_index, flight.constraint
+            )?;
+        }
+        Ok(())
+    }
+}
+
+struct Violations<'a>(&'a [Violation]);
+
+impl fmt::Display for Violations<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, violation) in self.0.iter().enumerate() {
+            let sep = if idx == 0 { ": " } else { ", " };
+            write!(f, "{}{}", sep, violation)?;
+        }
+        Ok(())
+    }
 }
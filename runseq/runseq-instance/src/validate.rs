@@ -0,0 +1,291 @@
+//! Diagnostics for malformed problem instances.
+//!
+//! See [`Instance::validate`] for details.
+
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+
+use thiserror::Error;
+
+use crate::{flight::Flight, pin::Pin, schedule::Schedule, Instance};
+
+/// A problem with the data of an [`Instance`].
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Error)]
+pub enum Diagnostic {
+    /// An aircraft's earliest time is after the latest time in its time window.
+    #[error("aircraft {} has earliest time {}, after its time window ends at {}", .flight_index, .earliest_time, .latest)]
+    EarliestAfterWindow {
+        /// The index of the aircraft.
+        flight_index: usize,
+        /// The earliest time of the aircraft.
+        earliest_time: NaiveDateTime,
+        /// The latest time in the aircraft's time window.
+        latest: NaiveDateTime,
+    },
+    /// A departure's Calculated Take-Off Time (CTOT) slot does not intersect its time window.
+    #[error("departure {} has a CTOT slot from {} to {} that does not intersect its time window from {} to {}", .flight_index, .ctot_earliest, .ctot_latest, .window_earliest, .window_latest)]
+    CtotOutsideWindow {
+        /// The index of the departure.
+        flight_index: usize,
+        /// The earliest time in the CTOT slot.
+        ctot_earliest: NaiveDateTime,
+        /// The latest time in the CTOT slot.
+        ctot_latest: NaiveDateTime,
+        /// The earliest time in the time window.
+        window_earliest: NaiveDateTime,
+        /// The latest time in the time window.
+        window_latest: NaiveDateTime,
+    },
+    /// A departure's Holdover Time (HOT) is too short for it to taxi out and line up after de-icing.
+    #[error("departure {} has a HOT of {:?}, but needs at least {:?} to taxi out and line up", .flight_index, .hot, .required)]
+    HotTooShort {
+        /// The index of the departure.
+        flight_index: usize,
+        /// The departure's HOT.
+        hot: Duration,
+        /// The sum of the departure's taxi and lineup durations.
+        required: Duration,
+    },
+    /// An aircraft is allowed to use a runway that does not exist, or is not allowed to use any runway at all.
+    #[error("aircraft {} has an invalid list of allowed runways", .flight_index)]
+    InvalidRunways {
+        /// The index of the aircraft.
+        flight_index: usize,
+    },
+    /// A pair of runways refers to a runway that does not exist.
+    #[error("runway pair {} refers to a runway that does not exist", .pair_index)]
+    InvalidRunwayPair {
+        /// The index of the pair of runways.
+        pair_index: usize,
+    },
+    /// A pin refers to an aircraft that does not exist or that is pinned more than once, or does not match the kind of
+    /// aircraft, its allowed runways, or its de-icing requirements.
+    #[error("aircraft {} has an invalid pin", .flight_index)]
+    InvalidPin {
+        /// The index of the pinned aircraft.
+        flight_index: usize,
+    },
+    /// A schedule in the reference sequence refers to an aircraft that does not exist or that is already scheduled
+    /// earlier in the reference sequence.
+    #[error("reference schedule at position {} refers to an unknown or repeated aircraft", .position)]
+    InvalidReference {
+        /// The position of the schedule in the reference sequence.
+        position: usize,
+    },
+    /// The separation between an aircraft and itself is zero.
+    #[error("aircraft {} has a zero separation with itself", .flight_index)]
+    ZeroDiagonal {
+        /// The index of the aircraft.
+        flight_index: usize,
+    },
+    /// The separation between two aircraft is greater than the separation via a third aircraft.
+    ///
+    /// At most one diagnostic is reported for each pair of aircraft, naming the third aircraft with the shortest
+    /// separation via it.
+    #[error("separation from aircraft {} to {} is {:?}, but only {:?} via aircraft {}", .from, .to, .direct, .indirect, .via)]
+    TriangleInequality {
+        /// The index of the aircraft that lands or takes off first.
+        from: usize,
+        /// The index of the aircraft that lands or takes off in between.
+        via: usize,
+        /// The index of the aircraft that lands or takes off last.
+        to: usize,
+        /// The separation from the first aircraft to the last.
+        direct: Duration,
+        /// The sum of the separations from the first aircraft to the one in between, and from the one in between to the
+        /// last.
+        indirect: Duration,
+    },
+}
+
+pub(crate) fn validate(instance: &Instance) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (flight_index, flight) in instance.flights().iter().enumerate() {
+        validate_flight(flight_index, flight, &mut diagnostics);
+
+        if let Some(runways) = flight.runways() {
+            let invalid = runways.is_empty()
+                || runways
+                    .iter()
+                    .any(|&runway| runway >= instance.runways().len());
+            if invalid {
+                diagnostics.push(Diagnostic::InvalidRunways { flight_index });
+            }
+        }
+    }
+
+    diagnostics.extend(
+        invalid_runway_pairs(instance)
+            .into_iter()
+            .map(|pair_index| Diagnostic::InvalidRunwayPair { pair_index }),
+    );
+
+    diagnostics.extend(
+        invalid_pins(instance)
+            .into_iter()
+            .map(|flight_index| Diagnostic::InvalidPin { flight_index }),
+    );
+
+    diagnostics.extend(
+        invalid_references(instance)
+            .into_iter()
+            .map(|position| Diagnostic::InvalidReference { position }),
+    );
+
+    validate_separations(instance, &mut diagnostics);
+
+    diagnostics
+}
+
+pub(crate) fn invalid_runway_pairs(instance: &Instance) -> Vec<usize> {
+    let runway_count = instance.runways().len();
+    instance
+        .runway_pairs()
+        .iter()
+        .enumerate()
+        .filter(|(_, pair)| pair.first >= runway_count || pair.second >= runway_count)
+        .map(|(pair_index, _)| pair_index)
+        .collect()
+}
+
+pub(crate) fn invalid_pins(instance: &Instance) -> Vec<usize> {
+    let mut pinned = vec![false; instance.flights().len()];
+    instance
+        .pinned()
+        .iter()
+        .filter(|pin| {
+            let flight_index = pin.flight_index();
+            let Some(flight) = instance.flights().get(flight_index) else {
+                return true;
+            };
+            if pinned[flight_index] {
+                return true;
+            }
+            pinned[flight_index] = true;
+
+            !is_valid_pin(pin, flight, instance)
+        })
+        .map(Pin::flight_index)
+        .collect()
+}
+
+pub(crate) fn invalid_references(instance: &Instance) -> Vec<usize> {
+    let mut referenced = vec![false; instance.flights().len()];
+    instance
+        .reference()
+        .iter()
+        .enumerate()
+        .filter(|(_, sched)| {
+            let Some(seen) = referenced.get_mut(sched.flight_index()) else {
+                return true;
+            };
+            let repeated = *seen;
+            *seen = true;
+            repeated
+        })
+        .map(|(position, _)| position)
+        .collect()
+}
+
+fn is_valid_pin(pin: &Pin, flight: &Flight, instance: &Instance) -> bool {
+    let valid_runway = match pin {
+        Pin::Schedule(sched) => instance
+            .allowed_runways(sched.flight_index())
+            .any(|runway| runway == sched.runway()),
+        Pin::Deice(_) => true,
+    };
+    let valid_deice = match (pin.deice(), flight.as_departure()) {
+        (None, _) => !matches!(
+            (pin, flight),
+            (Pin::Schedule(Schedule::Dep(_)), Flight::Dep(dep)) if dep.deice.is_some()
+        ),
+        (Some((_, deice_pad)), Some(dep)) => {
+            dep.deice.is_some() && deice_pad < instance.deice_pads().len()
+        },
+        (Some(_), None) => false,
+    };
+    let valid_kind = matches!(
+        (pin, flight),
+        (Pin::Schedule(Schedule::Arr(_)), Flight::Arr(_))
+            | (Pin::Schedule(Schedule::Dep(_)), Flight::Dep(_))
+            | (Pin::Deice(_), Flight::Dep(_))
+    );
+
+    valid_kind && valid_runway && valid_deice
+}
+
+fn validate_flight(flight_index: usize, flight: &Flight, diagnostics: &mut Vec<Diagnostic>) {
+    if let Some(window) = flight.window() {
+        if flight.earliest_time() > window.latest() {
+            diagnostics.push(Diagnostic::EarliestAfterWindow {
+                flight_index,
+                earliest_time: flight.earliest_time(),
+                latest: window.latest(),
+            });
+        }
+    }
+
+    let Some(dep) = flight.as_departure() else {
+        return;
+    };
+
+    if let Some((ctot, window)) = dep.ctot.as_ref().zip(dep.window.as_ref()) {
+        if ctot.latest() < window.earliest || ctot.earliest() > window.latest() {
+            diagnostics.push(Diagnostic::CtotOutsideWindow {
+                flight_index,
+                ctot_earliest: ctot.earliest(),
+                ctot_latest: ctot.latest(),
+                window_earliest: window.earliest,
+                window_latest: window.latest(),
+            });
+        }
+    }
+
+    if let Some(deice) = &dep.deice {
+        let required = dep.taxi_duration + dep.lineup_duration;
+        if deice.hot < required {
+            diagnostics.push(Diagnostic::HotTooShort {
+                flight_index,
+                hot: deice.hot,
+                required,
+            });
+        }
+    }
+}
+
+fn validate_separations(instance: &Instance, diagnostics: &mut Vec<Diagnostic>) {
+    let separations = instance.separations();
+    let len = separations.len();
+
+    diagnostics.extend(
+        (0..len)
+            .filter(|&flight_index| separations[(flight_index, flight_index)].is_zero())
+            .map(|flight_index| Diagnostic::ZeroDiagonal { flight_index }),
+    );
+
+    let pairs = (0..len)
+        .flat_map(|from| (0..len).map(move |to| (from, to)))
+        .filter(|&(from, to)| from != to);
+    for (from, to) in pairs {
+        let shortest = (0..len)
+            .filter(|&via| via != from && via != to)
+            .map(|via| (separations[(from, via)] + separations[(via, to)], via))
+            .min();
+        let Some((indirect, via)) = shortest else {
+            continue;
+        };
+
+        let direct = separations[(from, to)];
+        if direct > indirect {
+            diagnostics.push(Diagnostic::TriangleInequality {
+                from,
+                via,
+                to,
+                direct,
+                indirect,
+            });
+        }
+    }
+}
@@ -0,0 +1,613 @@
+//! Feasibility checking for runway sequences.
+//!
+//! Solutions may come from solvers implementing [`Solve`](crate::solve::Solve), from external solvers such as CPLEX,
+//! or from hand-edited files.
+//! [`verify`] checks such a sequence against the hard constraints of an [`Instance`] and reports every constraint
+//! that is violated, rather than stopping at the first one.
+
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+
+use serde::{Deserialize, Serialize};
+
+use serde_with::{serde_as, DurationSeconds};
+
+use thiserror::Error;
+
+use crate::{
+    flight::{Departure, Flight},
+    pin::Pin,
+    schedule::{DepartureSchedule, Schedule},
+    Instance,
+};
+
+/// A constraint violated by a runway sequence.
+#[serde_as] // NOTE: This must remain before the derives for `Serialize` and `Deserialize`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Error, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Violation {
+    /// An aircraft index does not refer to any aircraft in the instance.
+    #[error("aircraft {} does not exist in the instance", .flight_index)]
+    #[serde(rename_all = "kebab-case")]
+    UnknownFlight {
+        /// The index of the aircraft.
+        flight_index: usize,
+    },
+    /// An aircraft is scheduled more than once.
+    #[error("aircraft {} is scheduled more than once", .flight_index)]
+    #[serde(rename_all = "kebab-case")]
+    DuplicateFlight {
+        /// The index of the aircraft.
+        flight_index: usize,
+    },
+    /// An aircraft in the instance is not scheduled at all.
+    #[error("aircraft {} is not scheduled", .flight_index)]
+    #[serde(rename_all = "kebab-case")]
+    MissingFlight {
+        /// The index of the aircraft.
+        flight_index: usize,
+    },
+    /// An arrival is scheduled as a departure or vice-versa.
+    #[error("aircraft {} is scheduled as the wrong kind of aircraft", .flight_index)]
+    #[serde(rename_all = "kebab-case")]
+    KindMismatch {
+        /// The index of the aircraft.
+        flight_index: usize,
+    },
+    /// An aircraft is scheduled on a runway that it is not allowed to use, or that does not exist.
+    #[error("aircraft {} is not allowed to use runway {}", .flight_index, .runway)]
+    #[serde(rename_all = "kebab-case")]
+    RunwayNotAllowed {
+        /// The index of the aircraft.
+        flight_index: usize,
+        /// The index of the runway.
+        runway: usize,
+    },
+    /// An aircraft is scheduled to land or take off before its earliest time.
+    #[error("aircraft {} is scheduled at {}, before its earliest time {}", .flight_index, .time, .earliest_time)]
+    #[serde(rename_all = "kebab-case")]
+    TooEarly {
+        /// The index of the aircraft.
+        flight_index: usize,
+        /// The scheduled landing or take-off time.
+        time: NaiveDateTime,
+        /// The earliest time the aircraft can land or take off.
+        earliest_time: NaiveDateTime,
+    },
+    /// An aircraft is scheduled to land or take off outside its hard time window.
+    #[error("aircraft {} is scheduled at {}, outside its time window {} to {}", .flight_index, .time, .earliest, .latest)]
+    #[serde(rename_all = "kebab-case")]
+    OutsideWindow {
+        /// The index of the aircraft.
+        flight_index: usize,
+        /// The scheduled landing or take-off time.
+        time: NaiveDateTime,
+        /// The earliest time in the aircraft's time window.
+        earliest: NaiveDateTime,
+        /// The latest time in the aircraft's time window.
+        latest: NaiveDateTime,
+    },
+    /// A departure is scheduled to take off outside its Calculated Take-Off Time (CTOT) slot.
+    ///
+    /// # Note
+    ///
+    /// Taking off after the CTOT slot is usually penalised in the objective of a solver rather than forbidden outright,
+    /// whereas taking off before the CTOT slot is never allowed.
+    #[error("departure {} is scheduled at {}, outside its CTOT slot {} to {}", .flight_index, .takeoff, .earliest, .latest)]
+    #[serde(rename_all = "kebab-case")]
+    OutsideCtot {
+        /// The index of the departure.
+        flight_index: usize,
+        /// The scheduled take-off time.
+        takeoff: NaiveDateTime,
+        /// The earliest time in the CTOT slot.
+        earliest: NaiveDateTime,
+        /// The latest time in the CTOT slot.
+        latest: NaiveDateTime,
+    },
+    /// Two aircraft are scheduled closer together than their minimum separation.
+    #[error("aircraft {} and {} are separated by {:?}, but require at least {:?}", .from, .to, .actual, .required)]
+    #[serde(rename_all = "kebab-case")]
+    Separation {
+        /// The index of the aircraft that lands or takes off first.
+        from: usize,
+        /// The index of the aircraft that lands or takes off second.
+        to: usize,
+        /// The minimum separation required between the two aircraft.
+        #[serde_as(as = "DurationSeconds")]
+        required: Duration,
+        /// The actual separation between the two aircraft.
+        #[serde_as(as = "DurationSeconds")]
+        actual: Duration,
+    },
+    /// A departure that must be de-iced is not scheduled to de-ice.
+    #[error("departure {} must be de-iced, but has no de-icing time", .flight_index)]
+    #[serde(rename_all = "kebab-case")]
+    MissingDeice {
+        /// The index of the departure.
+        flight_index: usize,
+    },
+    /// A departure that does not need to be de-iced is scheduled to de-ice.
+    #[error("departure {} does not need to be de-iced, but has a de-icing time", .flight_index)]
+    #[serde(rename_all = "kebab-case")]
+    UnexpectedDeice {
+        /// The index of the departure.
+        flight_index: usize,
+    },
+    /// A departure is scheduled to de-ice at a de-icing pad that does not exist.
+    #[error("departure {} is scheduled to de-ice at pad {}, which does not exist", .flight_index, .deice_pad)]
+    #[serde(rename_all = "kebab-case")]
+    UnknownDeicePad {
+        /// The index of the departure.
+        flight_index: usize,
+        /// The index of the de-icing pad.
+        deice_pad: usize,
+    },
+    /// A departure is scheduled to de-ice too late to taxi out and line up before its take-off time.
+    #[error("departure {} cannot taxi out and line up in time after de-icing at {}", .flight_index, .deice)]
+    #[serde(rename_all = "kebab-case")]
+    DeiceTooLate {
+        /// The index of the departure.
+        flight_index: usize,
+        /// The scheduled de-icing time.
+        deice: NaiveDateTime,
+        /// The scheduled take-off time.
+        takeoff: NaiveDateTime,
+    },
+    /// A departure takes off after its Holdover Time (HOT) has expired.
+    #[error("departure {} takes off {:?} after de-icing, exceeding its HOT of {:?}", .flight_index, .elapsed, .hot)]
+    #[serde(rename_all = "kebab-case")]
+    Hot {
+        /// The index of the departure.
+        flight_index: usize,
+        /// The time between the end of de-icing and take-off.
+        #[serde_as(as = "DurationSeconds")]
+        elapsed: Duration,
+        /// The departure's HOT.
+        #[serde_as(as = "DurationSeconds")]
+        hot: Duration,
+    },
+    /// A departure waits at the runway for longer than allowed.
+    #[error("departure {} holds at the runway for {:?}, exceeding the maximum of {:?}", .flight_index, .runway_hold, .max_runway_hold)]
+    #[serde(rename_all = "kebab-case")]
+    RunwayHold {
+        /// The index of the departure.
+        flight_index: usize,
+        /// The departure's runway hold duration.
+        #[serde_as(as = "DurationSeconds")]
+        runway_hold: Duration,
+        /// The maximum runway hold duration allowed.
+        #[serde_as(as = "DurationSeconds")]
+        max_runway_hold: Duration,
+    },
+    /// A pinned aircraft is not scheduled according to its [`Pin`].
+    #[error("aircraft {} is not scheduled as pinned", .flight_index)]
+    #[serde(rename_all = "kebab-case")]
+    Pinned {
+        /// The index of the aircraft.
+        flight_index: usize,
+    },
+    /// An aircraft is sequenced further from its first-come-first-served position than allowed by
+    /// [`Instance::max_position_shift`].
+    #[error("aircraft {} is sequenced at position {}, more than {} positions from its first-come-first-served position {}", .flight_index, .position, .max_shift, .fcfs_position)]
+    #[serde(rename_all = "kebab-case")]
+    PositionShift {
+        /// The index of the aircraft.
+        flight_index: usize,
+        /// The position of the aircraft in the sequence.
+        position: usize,
+        /// The position of the aircraft in the first-come-first-served order.
+        fcfs_position: usize,
+        /// The maximum number of positions the aircraft may be moved.
+        max_shift: usize,
+    },
+    /// Two departures are scheduled to de-ice at overlapping times on the same de-icing pad.
+    #[error("departures {} and {} have overlapping de-icing times", .first, .second)]
+    #[serde(rename_all = "kebab-case")]
+    DeiceOverlap {
+        /// The index of the departure that starts de-icing first.
+        first: usize,
+        /// The index of the departure that starts de-icing second.
+        second: usize,
+    },
+}
+
+impl Violation {
+    /// Checks if the violation is of a constraint that is usually penalised in the objective of a solver rather than
+    /// forbidden outright.
+    ///
+    /// This is only the case for departures taking off after their CTOT slot.
+    pub fn is_soft(&self) -> bool {
+        matches!(self, Self::OutsideCtot { takeoff, latest, .. } if takeoff > latest)
+    }
+}
+
+/// Checks a runway sequence against the hard constraints of an [`Instance`], returning all constraints that are
+/// violated.
+///
+/// An empty list of violations means that the sequence is feasible.
+///
+/// Separations are checked between every pair of aircraft (not only adjacent ones), in the order of their scheduled
+/// landing or take-off times and taking the [relations between their runways](crate::runway::RunwayRelation) into
+/// account.
+/// Aircraft scheduled at the same time are ordered by their position in the sequence.
+///
+/// Position shifts are checked against the positions of the aircraft in the whole sequence, including schedules that
+/// are already reported as violations because they do not refer to a valid aircraft, since those still take up a
+/// position.
+pub fn verify(solution: &[Schedule], instance: &Instance) -> Vec<Violation> {
+    let mut violations = Vec::new();
+
+    // Check that every aircraft is scheduled exactly once and as the right kind of aircraft.
+    // Only schedules that refer to a valid aircraft of the right kind are checked further.
+    let mut scheduled = vec![false; instance.flights().len()];
+    let mut valid = Vec::with_capacity(solution.len());
+    for sched in solution {
+        let flight_index = sched.flight_index();
+        let Some(flight) = instance.flights().get(flight_index) else {
+            violations.push(Violation::UnknownFlight { flight_index });
+            continue;
+        };
+
+        if scheduled[flight_index] {
+            violations.push(Violation::DuplicateFlight { flight_index });
+            continue;
+        }
+        scheduled[flight_index] = true;
+
+        match (sched, flight) {
+            (Schedule::Arr(_), Flight::Arr(_)) | (Schedule::Dep(_), Flight::Dep(_)) => {
+                valid.push((sched, flight))
+            },
+            _ => violations.push(Violation::KindMismatch { flight_index }),
+        }
+    }
+
+    violations.extend(
+        scheduled
+            .iter()
+            .enumerate()
+            .filter(|(_, &scheduled)| !scheduled)
+            .map(|(flight_index, _)| Violation::MissingFlight { flight_index }),
+    );
+
+    for &(sched, flight) in &valid {
+        let flight_index = sched.flight_index();
+        let runway = sched.runway();
+        if !instance
+            .allowed_runways(flight_index)
+            .any(|allowed| allowed == runway)
+        {
+            violations.push(Violation::RunwayNotAllowed {
+                flight_index,
+                runway,
+            });
+        }
+
+        verify_times(sched, flight, &mut violations);
+        if let (Schedule::Dep(sched), Flight::Dep(dep)) = (sched, flight) {
+            verify_deice(sched, dep, instance, &mut violations);
+        }
+    }
+
+    verify_pins(&valid, instance, &mut violations);
+    verify_position_shifts(solution, instance, &mut violations);
+    verify_separations(&valid, instance, &mut violations);
+    verify_deice_overlaps(&valid, instance, &mut violations);
+
+    violations
+}
+
+fn verify_times(sched: &Schedule, flight: &Flight, violations: &mut Vec<Violation>) {
+    let flight_index = sched.flight_index();
+    let time = sched.flight_time();
+
+    if time < flight.earliest_time() {
+        violations.push(Violation::TooEarly {
+            flight_index,
+            time,
+            earliest_time: flight.earliest_time(),
+        });
+    }
+
+    if let Some(window) = flight.window() {
+        if !window.as_range().contains(&time) {
+            violations.push(Violation::OutsideWindow {
+                flight_index,
+                time,
+                earliest: window.earliest,
+                latest: window.latest(),
+            });
+        }
+    }
+
+    if let Some(ctot) = flight.as_departure().and_then(|dep| dep.ctot.as_ref()) {
+        if !ctot.as_range().contains(&time) {
+            violations.push(Violation::OutsideCtot {
+                flight_index,
+                takeoff: time,
+                earliest: ctot.earliest(),
+                latest: ctot.latest(),
+            });
+        }
+    }
+}
+
+fn verify_deice(
+    sched: &DepartureSchedule,
+    dep: &Departure,
+    instance: &Instance,
+    violations: &mut Vec<Violation>,
+) {
+    let flight_index = sched.flight_index;
+
+    let (deice, params) = match (sched.deice, &dep.deice) {
+        (None, None) => return,
+        (None, Some(_)) => {
+            violations.push(Violation::MissingDeice { flight_index });
+            return;
+        },
+        (Some(_), None) => {
+            violations.push(Violation::UnexpectedDeice { flight_index });
+            return;
+        },
+        (Some(deice), Some(params)) => (deice, params),
+    };
+
+    if sched.deice_pad >= instance.deice_pads().len() {
+        violations.push(Violation::UnknownDeicePad {
+            flight_index,
+            deice_pad: sched.deice_pad,
+        });
+        return;
+    }
+
+    let deice_end = deice + instance.deice_duration(params, sched.deice_pad);
+
+    // The runway hold is the time left over after de-icing, taxiing out, and lining up.
+    // If it is negative, then the departure cannot possibly make it to the runway in time.
+    let Ok(runway_hold) =
+        (sched.takeoff - dep.lineup_duration - dep.taxi_duration - deice_end).to_std()
+    else {
+        violations.push(Violation::DeiceTooLate {
+            flight_index,
+            deice,
+            takeoff: sched.takeoff,
+        });
+        return;
+    };
+
+    // NOTE: This cannot fail, since the runway hold is non-negative.
+    let elapsed = (sched.takeoff - deice_end).to_std().unwrap();
+    if elapsed > params.hot {
+        violations.push(Violation::Hot {
+            flight_index,
+            elapsed,
+            hot: params.hot,
+        });
+    }
+
+    if runway_hold > instance.max_runway_hold_duration {
+        violations.push(Violation::RunwayHold {
+            flight_index,
+            runway_hold,
+            max_runway_hold: instance.max_runway_hold_duration,
+        });
+    }
+}
+
+fn verify_pins(
+    valid: &[(&Schedule, &Flight)],
+    instance: &Instance,
+    violations: &mut Vec<Violation>,
+) {
+    for &(sched, _) in valid {
+        let Some(pin) = instance.pin(sched.flight_index()) else {
+            continue;
+        };
+
+        let pinned = match pin {
+            Pin::Schedule(pinned) => sched == pinned,
+            Pin::Deice(slot) => sched.as_departure().is_some_and(|sched| {
+                sched.deice == Some(slot.deice) && sched.deice_pad == slot.deice_pad
+            }),
+        };
+        if !pinned {
+            violations.push(Violation::Pinned {
+                flight_index: sched.flight_index(),
+            });
+        }
+    }
+}
+
+fn verify_position_shifts(
+    solution: &[Schedule],
+    instance: &Instance,
+    violations: &mut Vec<Violation>,
+) {
+    let Some(max_shift) = instance.max_position_shift else {
+        return;
+    };
+
+    // NOTE: Aircraft with pinned schedules are always sequenced first, so they are checked against their pins instead.
+    //       Positions are counted in the whole solution, including schedules that are invalid, since those still take
+    //       up positions in the sequence.
+    let fcfs_positions = instance.fcfs_positions();
+    for (position, sched) in solution.iter().enumerate() {
+        let flight_index = sched.flight_index();
+        let Some(&fcfs_position) = fcfs_positions.get(flight_index) else {
+            continue;
+        };
+        let pinned = instance
+            .pin(flight_index)
+            .is_some_and(|pin| pin.as_schedule().is_some());
+        if !pinned && position.abs_diff(fcfs_position) > max_shift {
+            violations.push(Violation::PositionShift {
+                flight_index,
+                position,
+                fcfs_position,
+                max_shift,
+            });
+        }
+    }
+}
+
+fn verify_separations(
+    valid: &[(&Schedule, &Flight)],
+    instance: &Instance,
+    violations: &mut Vec<Violation>,
+) {
+    // NOTE: A stable sort is used so that aircraft scheduled at the same time keep their order in the sequence.
+    //       Schedules on runways that do not exist are ignored, since they are already reported as violations.
+    let mut by_time = valid
+        .iter()
+        .map(|(sched, _)| *sched)
+        .filter(|sched| sched.runway() < instance.runways().len())
+        .collect::<Vec<_>>();
+    by_time.sort_by_key(|sched| sched.flight_time());
+
+    for (idx, first) in by_time.iter().enumerate() {
+        for second in &by_time[idx + 1..] {
+            let (from, to) = (first.flight_index(), second.flight_index());
+            let Some(required) = instance.separation(from, first.runway(), to, second.runway())
+            else {
+                continue;
+            };
+
+            // NOTE: This cannot fail, since the schedules are sorted by their landing or take-off times.
+            let actual = (second.flight_time() - first.flight_time())
+                .to_std()
+                .unwrap();
+            if actual < required {
+                violations.push(Violation::Separation {
+                    from,
+                    to,
+                    required,
+                    actual,
+                });
+            }
+        }
+    }
+}
+
+fn verify_deice_overlaps(
+    valid: &[(&Schedule, &Flight)],
+    instance: &Instance,
+    violations: &mut Vec<Violation>,
+) {
+    // NOTE: Departures at de-icing pads that do not exist are ignored, since they are already reported as violations.
+    let mut deices = valid
+        .iter()
+        .filter_map(|(sched, flight)| {
+            let sched = sched.as_departure()?;
+            let deice = sched.deice?;
+            let params = flight.as_departure()?.deice.as_ref()?;
+            let duration = instance.deice_duration(params, sched.deice_pad);
+            Some((sched.flight_index, sched.deice_pad, deice, deice + duration))
+        })
+        .filter(|&(_, pad, ..)| pad < instance.deice_pads().len())
+        .collect::<Vec<_>>();
+    deices.sort_by_key(|&(_, pad, deice, _)| (pad, deice));
+
+    for (idx, &(first, first_pad, _, first_end)) in deices.iter().enumerate() {
+        let overlapping = deices[idx + 1..]
+            .iter()
+            .take_while(|&&(_, pad, deice, _)| pad == first_pad && deice < first_end)
+            .map(|&(second, ..)| Violation::DeiceOverlap { first, second });
+        violations.extend(overlapping);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    use crate::{
+        flight::{Arrival, Flight},
+        schedule::{ArrivalSchedule, Schedule},
+        sep::Separations,
+        verify::{verify, Violation},
+        Instance,
+    };
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    fn start() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, 1)
+            .and_then(|date| date.and_hms_opt(10, 0, 0))
+            .unwrap()
+    }
+
+    // Creates an instance of arrivals that can all land from the start, separated by a minute, whose
+    // first-come-first-served order is the order of their indices
+    fn instance(flight_count: usize, max_position_shift: usize) -> Instance {
+        let flights = (0..flight_count)
+            .map(|_| {
+                Flight::Arr(Arrival {
+                    earliest_time: start(),
+                    base_time: start(),
+                    window: None,
+                    runways: None,
+                    info: None,
+                })
+            })
+            .collect::<Vec<_>>();
+        let separations = Separations::new(vec![MINUTE; flight_count * flight_count], flight_count);
+
+        let mut instance = Instance::new(flights, separations.unwrap(), MINUTE * 10).unwrap();
+        instance.max_position_shift = Some(max_position_shift);
+        instance
+    }
+
+    fn sequence(flight_indices: &[usize]) -> Vec<Schedule> {
+        flight_indices
+            .iter()
+            .zip(0..)
+            .map(|(&flight_index, minute)| {
+                Schedule::Arr(ArrivalSchedule {
+                    flight_index,
+                    landing: start() + MINUTE * minute,
+                    runway: 0,
+                })
+            })
+            .collect()
+    }
+
+    fn position_shifts(violations: &[Violation]) -> Vec<(usize, usize)> {
+        violations
+            .iter()
+            .filter_map(|violation| match violation {
+                Violation::PositionShift {
+                    flight_index,
+                    position,
+                    ..
+                } => Some((*flight_index, *position)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn position_shifts_are_checked_against_the_first_come_first_served_order() {
+        let instance = instance(3, 1);
+
+        assert!(verify(&sequence(&[1, 0, 2]), &instance).is_empty());
+        assert_eq!(
+            position_shifts(&verify(&sequence(&[2, 0, 1]), &instance)),
+            [(2, 0)],
+        );
+    }
+
+    #[test]
+    fn position_shifts_count_invalid_schedules() {
+        let instance = instance(3, 0);
+
+        // NOTE: The unknown aircraft still takes up the first position, so every other aircraft is moved back by one.
+        let violations = verify(&sequence(&[7, 0, 1, 2]), &instance);
+        assert!(violations.contains(&Violation::UnknownFlight { flight_index: 7 }));
+        assert_eq!(position_shifts(&violations), [(0, 1), (1, 2), (2, 3)]);
+    }
+}
@@ -0,0 +1,263 @@
+//! A local search for improving existing runway sequences.
+
+#![deny(rust_2018_idioms)]
+#![warn(missing_docs)]
+
+use std::{
+    collections::BTreeMap,
+    num::NonZeroUsize,
+    time::{Duration, Instant},
+};
+
+use runseq_branch_bound::{decode, Cost, Objective};
+use runseq_instance::{
+    schedule::Schedule,
+    solution::{Solution, SolverInfo},
+    solve::{Solve, SolveError},
+    verify::verify,
+    Instance,
+};
+
+/// A local search that improves existing runway sequences, such as those produced by any [`Solve`]r.
+///
+/// Starting from the order of the given sequence, the search repeatedly makes the first move it finds that results in
+/// a cheaper sequence, until no move does so or the time limit is reached.
+/// After every move, all aircraft are re-timed using [`decode`], so only feasible sequences are ever accepted and
+/// their cost is the same as given by [`Objective::solution_cost`].
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct LocalSearch {
+    /// The objective function to minimise.
+    pub objective: Objective,
+    /// The neighbourhoods to search for moves, in the order they are searched in.
+    pub neighbourhoods: Vec<Neighbourhood>,
+    /// The maximum number of positions that an aircraft or block of aircraft can be moved by in a single move.
+    pub max_distance: NonZeroUsize,
+    /// The maximum wall-clock time to spend improving a sequence, if any.
+    pub time_limit: Option<Duration>,
+}
+
+impl Default for LocalSearch {
+    fn default() -> Self {
+        Self {
+            objective: Objective::default(),
+            neighbourhoods: vec![
+                Neighbourhood::Swap,
+                Neighbourhood::Insert,
+                Neighbourhood::BlockMove {
+                    max_len: NonZeroUsize::new(3).unwrap(),
+                },
+            ],
+            max_distance: NonZeroUsize::new(8).unwrap(),
+            time_limit: None,
+        }
+    }
+}
+
+/// Different kinds of moves that the [`LocalSearch`] can make to a sequence.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Neighbourhood {
+    /// Swap the positions of two aircraft.
+    Swap,
+    /// Remove an aircraft from the sequence and insert it at another position.
+    Insert,
+    /// Move a block of consecutive aircraft to another position in the sequence.
+    BlockMove {
+        /// The maximum number of aircraft in a block.
+        max_len: NonZeroUsize,
+    },
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+enum Move {
+    Swap(usize, usize),
+    Shift { from: usize, len: usize, to: usize },
+}
+
+impl Move {
+    fn apply(self, order: &[usize]) -> Vec<usize> {
+        let mut order = order.to_vec();
+        match self {
+            Self::Swap(first, second) => order.swap(first, second),
+            Self::Shift { from, len, to } => {
+                let block = order.drain(from..from + len).collect::<Vec<_>>();
+                order.splice(to..to, block);
+            },
+        }
+        order
+    }
+}
+
+impl LocalSearch {
+    /// Improves a runway sequence, returning the best sequence found.
+    ///
+    /// The returned sequence is never worse than the given one, which is returned unchanged if no improvement could be
+    /// found.
+    ///
+    /// Returns a [`SolveError`] if the given sequence is infeasible, although it may violate constraints that are
+    /// [penalised](Objective::penalises) by the objective, such as departures taking off after their CTOT slot.
+    pub fn improve(
+        &self,
+        schedules: &[Schedule],
+        instance: &Instance,
+    ) -> Result<Vec<Schedule>, SolveError> {
+        let start = Instant::now();
+
+        let mut violations = verify(schedules, instance);
+        violations.retain(|violation| !self.objective.penalises(violation));
+        if !violations.is_empty() {
+            return Err(SolveError::InfeasibleWarmStart { violations });
+        }
+
+        let mut order = schedules
+            .iter()
+            .map(Schedule::flight_index)
+            .collect::<Vec<_>>();
+        let mut best_solution = schedules.to_vec();
+        let mut best_cost = self.objective.solution_cost(schedules, instance);
+
+        // Re-time the given sequence first, since it may not have been timed as cheaply as possible
+        if let Some((solution, cost)) = self.evaluate(&order, instance) {
+            if cost < best_cost {
+                best_solution = solution;
+                best_cost = cost;
+            }
+        }
+
+        // Keep making the first improving move found until there are none left
+        'search: loop {
+            for &neighbourhood in &self.neighbourhoods {
+                for mv in self.moves(neighbourhood, order.len()) {
+                    if self
+                        .time_limit
+                        .is_some_and(|time_limit| start.elapsed() >= time_limit)
+                    {
+                        break 'search;
+                    }
+
+                    let candidate = mv.apply(&order);
+                    let Some((solution, cost)) = self.evaluate(&candidate, instance) else {
+                        continue;
+                    };
+                    if cost < best_cost {
+                        order = candidate;
+                        best_solution = solution;
+                        best_cost = cost;
+                        continue 'search;
+                    }
+                }
+            }
+            break;
+        }
+
+        Ok(best_solution)
+    }
+
+    fn evaluate(&self, order: &[usize], instance: &Instance) -> Option<(Vec<Schedule>, Cost)> {
+        let solution = decode(order, instance, &self.objective).ok()?;
+        let cost = self.objective.solution_cost(&solution, instance);
+        Some((solution, cost))
+    }
+
+    fn moves(&self, neighbourhood: Neighbourhood, len: usize) -> impl Iterator<Item = Move> + '_ {
+        let max_distance = self.max_distance.get();
+        let (min_block, max_block) = match neighbourhood {
+            Neighbourhood::Swap | Neighbourhood::Insert => (1, 1),
+            Neighbourhood::BlockMove { max_len } => (2, max_len.get()),
+        };
+
+        // NOTE: A block of aircraft is moved by removing it from the sequence and inserting it back in at a position in
+        //       the remaining sequence, so there are `len - block_len + 1` positions to insert it at.
+        (0..len).flat_map(move |from| {
+            let swaps = match neighbourhood {
+                Neighbourhood::Swap => from + 1..len.min(from + max_distance + 1),
+                Neighbourhood::Insert | Neighbourhood::BlockMove { .. } => 0..0,
+            }
+            .map(move |to| Move::Swap(from, to));
+
+            let shifts = match neighbourhood {
+                Neighbourhood::Swap => 0..0,
+                Neighbourhood::Insert | Neighbourhood::BlockMove { .. } => min_block..max_block + 1,
+            }
+            .filter(move |block_len| from + block_len <= len)
+            .flat_map(move |block_len| {
+                let positions = len - block_len + 1;
+                (from.saturating_sub(max_distance)..positions.min(from + max_distance + 1))
+                    .filter(move |&to| to != from)
+                    .map(move |to| Move::Shift {
+                        from,
+                        len: block_len,
+                        to,
+                    })
+            });
+
+            swaps.chain(shifts)
+        })
+    }
+
+    fn settings(&self) -> BTreeMap<String, String> {
+        let neighbourhoods = self
+            .neighbourhoods
+            .iter()
+            .map(|neighbourhood| match neighbourhood {
+                Neighbourhood::Swap => "swap".to_owned(),
+                Neighbourhood::Insert => "insert".to_owned(),
+                Neighbourhood::BlockMove { max_len } => format!("block-move-{}", max_len),
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let mut settings = BTreeMap::from([
+            ("neighbourhoods".to_owned(), neighbourhoods),
+            ("max-distance".to_owned(), self.max_distance.to_string()),
+        ]);
+        if let Some(time_limit) = self.time_limit {
+            settings.insert("time-limit".to_owned(), format!("{:?}", time_limit));
+        }
+        if self.objective != Objective::default() {
+            settings.insert("objective".to_owned(), format!("{:?}", self.objective));
+        }
+        settings
+    }
+}
+
+/// A solver that improves the sequences produced by another [`Solve`]r using a [`LocalSearch`].
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Improved<S> {
+    /// The solver that produces the sequence to improve.
+    pub solver: S,
+    /// The local search used to improve the sequence.
+    pub local_search: LocalSearch,
+}
+
+impl<S> Solve for Improved<S>
+where
+    S: Solve,
+{
+    fn solve(&self, instance: &Instance) -> Result<Solution, SolveError> {
+        let start = Instant::now();
+
+        let initial = self.solver.solve(instance)?;
+        let schedules = self.local_search.improve(&initial.schedules, instance)?;
+
+        // NOTE: An optimal sequence can only stay optimal if the local search did not change it at all.
+        let optimal = initial.optimal && schedules == initial.schedules;
+
+        let mut settings = self.local_search.settings();
+        if let Some(solver) = &initial.solver {
+            settings.insert("solver".to_owned(), solver.name.clone());
+            for (name, value) in &solver.settings {
+                settings.insert(format!("solver.{}", name), value.clone());
+            }
+        }
+
+        let mut solution = Solution::new(schedules, instance);
+        solution.solver = Some(SolverInfo {
+            name: "local-search".to_owned(),
+            settings,
+        });
+        solution.wall_time = Some(start.elapsed());
+        solution.optimal = optimal;
+
+        Ok(solution)
+    }
+}
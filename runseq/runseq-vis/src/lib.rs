@@ -11,7 +11,7 @@ use svg::{
 };
 
 use runseq_instance::{
-    flight::{Arrival, Departure},
+    flight::{Arrival, Departure, Flight},
     schedule::{ArrivalSchedule, DepartureSchedule, Schedule},
     Instance,
 };
@@ -73,6 +73,7 @@ impl Visualiser {
     ///
     /// This method will panic if the number of aircraft in the sequence does not match the number of aircraft in the instance,
     /// which can happen if the given runway sequence was not produced by solving the given instance.
+    /// Use [`verify`](runseq_instance::verify::verify) to check a runway sequence against an instance beforehand.
     pub fn visualise(&self, schedule: &[Schedule], instance: &Instance) -> Option<Document> {
         let start = start_time(schedule, instance)?;
         let end = end_time(schedule, instance)?;
@@ -96,13 +97,13 @@ impl Visualiser {
             let group = match sched {
                 Schedule::Arr(sched) => {
                     let arr = instance.flights()[sched.flight_index].as_arrival().unwrap();
-                    self.visualise_arrival(sched, arr, row, start)
+                    self.visualise_arrival(sched, arr, instance, row, start)
                 },
                 Schedule::Dep(sched) => {
                     let dep = instance.flights()[sched.flight_index]
                         .as_departure()
                         .unwrap();
-                    self.visualise_departure(sched, dep, row, start)
+                    self.visualise_departure(sched, dep, instance, row, start)
                 },
             };
             doc.add(group)
@@ -115,16 +116,25 @@ impl Visualiser {
         &self,
         sched: &ArrivalSchedule,
         arr: &Arrival,
+        instance: &Instance,
         row: usize,
         start: NaiveDateTime,
     ) -> Group {
         let row = row as f64;
 
+        let label = flight_label(sched.flight_index, &instance.flights()[sched.flight_index]);
+        let runway = runway_name(sched.runway, instance);
+
         let landing = {
             let x = width(sched.landing, start) * SCALE_X;
             let y = row * SCALE_Y;
 
-            let title = title!("Landing ({})", sched.landing.format(FMT));
+            let title = title!(
+                "{}\nLanding ({}, runway {})",
+                label,
+                sched.landing.format(FMT),
+                runway
+            );
 
             let bar = line(x, y + SHORT_BAR_OFFSET, SHORT_BAR_HEIGHT);
             let square = square(x - (SQUARE_SIZE * 0.5), y + SQUARE_OFFSET, SQUARE_SIZE)
@@ -167,16 +177,30 @@ impl Visualiser {
         &self,
         sched: &DepartureSchedule,
         dep: &Departure,
+        instance: &Instance,
         row: usize,
         start: NaiveDateTime,
     ) -> Group {
         let row = row as f64;
 
+        let label = flight_label(sched.flight_index, &instance.flights()[sched.flight_index]);
+        let runway = runway_name(sched.runway, instance);
+
+        let deice_dur = dep
+            .deice
+            .as_ref()
+            .map(|deice| instance.deice_duration(deice, sched.deice_pad));
+
         let takeoff = {
             let x = width(sched.takeoff, start) * SCALE_X;
             let y = row * SCALE_Y;
 
-            let title = title!("Take-off ({})", sched.takeoff.format(FMT));
+            let title = title!(
+                "{}\nTake-off ({}, runway {})",
+                label,
+                sched.takeoff.format(FMT),
+                runway
+            );
 
             let bar = line(x, y + SHORT_BAR_OFFSET, SHORT_BAR_HEIGHT);
             let square = square(x - (SQUARE_SIZE * 0.5), y + SQUARE_OFFSET, SQUARE_SIZE)
@@ -191,7 +215,11 @@ impl Visualiser {
                 let x = width(deice, start) * SCALE_X;
                 let y = row * SCALE_Y;
 
-                let title = title!("De-ice ({})", deice.format(FMT));
+                let title = title!(
+                    "De-ice ({}, pad {})",
+                    deice.format(FMT),
+                    deice_pad_name(sched.deice_pad, instance)
+                );
 
                 let bar = line(x, y + SHORT_BAR_OFFSET, SHORT_BAR_HEIGHT);
                 let square = square(x - (SQUARE_SIZE * 0.5), y + SQUARE_OFFSET, SQUARE_SIZE)
@@ -231,7 +259,7 @@ impl Visualiser {
         let runway_hold = match sched.deice {
             None => Group::new(),
             Some(deice) => {
-                let taxi_end = deice + dep.deice.as_ref().unwrap().duration + dep.taxi_duration;
+                let taxi_end = deice + deice_dur.unwrap() + dep.taxi_duration;
 
                 let x = width(taxi_end, start) * SCALE_X;
                 let y = (row * SCALE_Y) + RECT_OFFSET;
@@ -310,7 +338,7 @@ impl Visualiser {
                 let x = width(deice, start) * SCALE_X;
                 let y = (row * SCALE_Y) + RECT_OFFSET;
 
-                let width = dep.deice.as_ref().unwrap().duration.as_secs_f64() * SCALE_X;
+                let width = deice_dur.unwrap().as_secs_f64() * SCALE_X;
 
                 let title = title!("De-ice ({} seconds)", width / SCALE_X);
 
@@ -332,7 +360,7 @@ impl Visualiser {
                     sched.takeoff - dep.lineup_duration - dep.taxi_duration,
                     start,
                 ),
-                Some(deice) => width(deice + dep.deice.as_ref().unwrap().duration, start),
+                Some(deice) => width(deice + deice_dur.unwrap(), start),
             } * SCALE_X;
             let y = (row * SCALE_Y) + RECT_OFFSET;
 
@@ -478,6 +506,54 @@ fn end_time(schedule: &[Schedule], instance: &Instance) -> Option<NaiveDateTime>
         .max()
 }
 
+fn flight_label(flight_index: usize, flight: &Flight) -> String {
+    let Some(info) = flight.info() else {
+        return format!("Aircraft {}", flight_index);
+    };
+
+    let mut label = match info.name() {
+        Some(name) => name.to_owned(),
+        None => format!("Aircraft {}", flight_index),
+    };
+
+    let details = [
+        info.aircraft_type.clone(),
+        info.weight_class.clone(),
+        info.stand.as_ref().map(|stand| format!("stand {}", stand)),
+        info.route.as_ref().map(|route| format!("route {}", route)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+    if !details.is_empty() {
+        label = format!("{} ({})", label, details.join(", "));
+    }
+
+    label
+}
+
+fn runway_name(runway: usize, instance: &Instance) -> String {
+    match instance
+        .runways()
+        .get(runway)
+        .and_then(|runway| runway.name.as_ref())
+    {
+        Some(name) => name.clone(),
+        None => runway.to_string(),
+    }
+}
+
+fn deice_pad_name(deice_pad: usize, instance: &Instance) -> String {
+    match instance
+        .deice_pads()
+        .get(deice_pad)
+        .and_then(|deice_pad| deice_pad.name.as_ref())
+    {
+        Some(name) => name.clone(),
+        None => deice_pad.to_string(),
+    }
+}
+
 fn width(to: NaiveDateTime, from: NaiveDateTime) -> f64 {
     (to - from).num_seconds().unsigned_abs() as f64
 }
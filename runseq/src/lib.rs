@@ -6,6 +6,10 @@
 #[doc(inline)]
 pub use runseq_instance as instance;
 
+#[cfg(feature = "anneal")]
+#[doc(inline)]
+pub use runseq_anneal as anneal;
+
 #[cfg(feature = "data")]
 #[doc(inline)]
 pub use runseq_data as data;
@@ -14,6 +18,14 @@ pub use runseq_data as data;
 #[doc(inline)]
 pub use runseq_branch_bound as branch_bound;
 
+#[cfg(feature = "fcfs")]
+#[doc(inline)]
+pub use runseq_fcfs as fcfs;
+
+#[cfg(feature = "local-search")]
+#[doc(inline)]
+pub use runseq_local_search as local_search;
+
 #[cfg(feature = "vis")]
 #[doc(inline)]
 pub use runseq_vis as vis;